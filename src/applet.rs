@@ -30,12 +30,19 @@ const APP_ID: &str = "io.github.reality2_roycdavies.cosmic-pie-menu";
 /// Messages sent from the gesture detection thread to the applet
 #[derive(Debug, Clone)]
 pub enum GestureMessage {
-    /// Pie menu should be shown (gesture completed)
-    ShowPieMenu,
+    /// Pie menu should be shown (gesture completed), with the menu to open
+    ShowPieMenu(String),
     /// Fingers touched down (for potential visual feedback)
     FingersDown,
     /// Gesture was cancelled or menu closed
     Reset,
+    /// The held trigger was let go (marking-menu mode), with the direction it
+    /// moved in (radians, screen coordinates) if it moved far enough
+    Release(Option<f32>),
+    /// A touchpad drag after the tap points in this direction (radians, screen coordinates)
+    Point(f32),
+    /// The held trigger turned into another gesture; close the pie it opened
+    HidePieMenu,
 }
 
 /// Applet UI messages
@@ -104,6 +111,13 @@ impl cosmic::Application for PieMenuApplet {
             Err(e) => eprintln!("Gesture detection not available: {}", e),
         }
 
+        // Keep the desktop file index current for the pie subprocesses
+        if let Err(e) = crate::desktop_index::start_watcher() {
+            eprintln!("Desktop file watching not available: {}", e);
+        }
+
+        spawn_pie_service();
+
         let gesture_rx = Arc::new(tokio::sync::Mutex::new(rx));
         let _ = GESTURE_RX.set(gesture_rx.clone());
         let applet = PieMenuApplet {
@@ -143,9 +157,9 @@ impl cosmic::Application for PieMenuApplet {
         match message {
             Message::GestureEvent(gesture_msg) => {
                 match gesture_msg {
-                    GestureMessage::ShowPieMenu => {
+                    GestureMessage::ShowPieMenu(menu) => {
                         self.gesture_active = false;
-                        spawn_pie_menu();
+                        spawn_pie_menu(&menu);
                     }
                     GestureMessage::FingersDown => {
                         self.gesture_active = true;
@@ -153,6 +167,17 @@ impl cosmic::Application for PieMenuApplet {
                     GestureMessage::Reset => {
                         self.gesture_active = false;
                     }
+                    GestureMessage::Release(angle) => {
+                        self.gesture_active = false;
+                        send_to_pie_service(crate::daemon::Request::Release { angle });
+                    }
+                    GestureMessage::Point(angle) => {
+                        send_to_pie_service(crate::daemon::Request::Point { angle });
+                    }
+                    GestureMessage::HidePieMenu => {
+                        self.gesture_active = false;
+                        send_to_pie_service(crate::daemon::Request::Hide);
+                    }
                 }
             }
             Message::ShowPieMenu => {
@@ -162,7 +187,7 @@ impl cosmic::Application for PieMenuApplet {
                 } else {
                     Task::none()
                 };
-                spawn_pie_menu();
+                spawn_pie_menu(&PieMenuConfig::load().applet_menu);
                 return task;
             }
             Message::TogglePopup => {
@@ -228,8 +253,61 @@ impl cosmic::Application for PieMenuApplet {
     }
 }
 
-/// Spawn the pie menu as a subprocess
-fn spawn_pie_menu() {
+/// Start the pie service so pies open without starting new processes
+///
+/// Does nothing if it's already running (the service holds a lock).
+fn spawn_pie_service() {
+    let exe = std::env::current_exe().unwrap_or_else(|_| "cosmic-pie-menu".into());
+    if let Err(e) = Command::new(exe).arg("--daemon").spawn() {
+        eprintln!("Failed to start pie service: {}", e);
+    }
+}
+
+/// Pass a request to the pie service (marking-menu mode and touchpad selection need it running)
+fn send_to_pie_service(request: crate::daemon::Request) {
+    if let Err(e) = crate::daemon::send(&request) {
+        eprintln!("Pie service not available for {}: {}", request.to_line(), e);
+    }
+}
+
+/// Turn off the modes that need the pie service while it isn't running
+///
+/// Marking-menu mode and touchpad selection send the release and drag to the
+/// service, which a fallback pie process never hears. The gesture thread
+/// plays them as plain taps until a pie opens through the service again.
+/// Warns with a notification the first time.
+fn pie_service_missing() {
+    let config = PieMenuConfig::load();
+    if !(config.marking_menu || config.touchpad_select) {
+        return;
+    }
+    if crate::gesture::set_pie_service_missing(true) {
+        return;
+    }
+    eprintln!("Pie service not running: marking-menu mode and touchpad selection are off until it is back");
+    let _ = Command::new("notify-send")
+        .args([
+            "--app-name=Pie Menu",
+            "Pie service not running",
+            "Marking-menu mode and touchpad selection are off until it is back.",
+        ])
+        .spawn();
+}
+
+/// Show the named pie menu
+///
+/// Asks the pie service, which closes any open pie itself. Without the service,
+/// spawns the menu as a subprocess.
+fn spawn_pie_menu(menu: &str) {
+    match crate::daemon::send(&crate::daemon::Request::Show { menu: menu.to_string() }) {
+        Ok(()) => {
+            crate::gesture::set_pie_service_missing(false);
+            return;
+        }
+        Err(e) => println!("Pie service not available ({}), starting a pie process", e),
+    }
+    pie_service_missing();
+
     // Kill any existing pie menu instances first
     let _ = Command::new("pkill")
         .args(["-f", "cosmic-pie-menu --track"])
@@ -240,7 +318,7 @@ fn spawn_pie_menu() {
 
     println!("Launching pie menu overlay...");
     let exe = std::env::current_exe().unwrap_or_else(|_| "cosmic-pie-menu".into());
-    if let Err(e) = Command::new(exe).arg("--track").spawn() {
+    if let Err(e) = Command::new(exe).args(["--track", "--menu", menu]).spawn() {
         eprintln!("Failed to launch pie menu: {}", e);
     }
 }
@@ -5,7 +5,94 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{PieGroup, PieMenuConfig, RunningPlacement};
+use crate::desktop_index;
+use crate::history::{self, LaunchHistory, LaunchRecord, RunningOrder};
+use crate::windows::{WindowAction, WindowInfo};
+
+/// What selecting a pie item does
+#[derive(Debug, Clone, Default)]
+pub enum ItemKind {
+    /// Launch (or switch to) an application
+    #[default]
+    App,
+    /// Run `exec` through the shell
+    Command,
+    /// Open a URL, file or folder with its default handler (xdg-open)
+    Open(String),
+    /// Perform an action provided by the pie menu itself
+    Builtin(BuiltinAction),
+    /// Activate this specific open window
+    Window(WindowInfo),
+    /// Manage an open window (close, minimize, ...)
+    WindowAction(WindowInfo, WindowAction),
+    /// Open a nested pie containing these child items
+    Submenu(Vec<AppInfo>),
+    /// Empty gap in the ring (not selectable)
+    Separator,
+}
+
+/// Actions provided by the pie menu itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinAction {
+    /// Lock the session
+    LockScreen,
+    /// Take a screenshot (after the pie has closed)
+    Screenshot,
+    /// Close the pie without doing anything
+    ClosePie,
+}
+
+impl BuiltinAction {
+    /// Default display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LockScreen => "Lock Screen",
+            Self::Screenshot => "Screenshot",
+            Self::ClosePie => "Close",
+        }
+    }
+
+    /// Default icon name
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::LockScreen => "system-lock-screen",
+            Self::Screenshot => "applets-screenshooter",
+            Self::ClosePie => "window-close",
+        }
+    }
+
+    /// Shell command that performs the action, if it runs one
+    pub fn command(&self) -> Option<&'static str> {
+        match self {
+            Self::LockScreen => Some("loginctl lock-session"),
+            Self::Screenshot => Some("cosmic-screenshot"),
+            Self::ClosePie => None,
+        }
+    }
+}
+
+/// Expand a leading `~` in a path-like open target to the home directory
+pub fn expand_home(target: &str) -> String {
+    match (target.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{}", home.display(), rest)
+        }
+        _ => target.to_string(),
+    }
+}
+
+/// Whether an open target is a URL rather than a local path
+pub fn is_url(target: &str) -> bool {
+    target.contains("://") && !target.starts_with("file://")
+}
 
 /// Information about an application
 #[derive(Debug, Clone)]
@@ -25,140 +112,798 @@ pub struct AppInfo {
     pub running_count: u32,
     /// Whether this app is a dock favorite (vs just running)
     pub is_favorite: bool,
+    /// What selecting this item does
+    pub kind: ItemKind,
+    /// Extra actions from the desktop file (e.g. "New Private Window")
+    pub actions: Vec<DesktopAction>,
+    /// Whether `exec` runs in a terminal (`Terminal=true`)
+    pub terminal: bool,
+    /// Working directory to launch in (`Path=`)
+    pub working_dir: Option<PathBuf>,
+    /// Search terms from the desktop file (`Keywords=`)
+    pub keywords: Vec<String>,
 }
 
-/// Get all standard locations for desktop files
-fn desktop_file_dirs() -> Vec<PathBuf> {
-    let mut dirs = Vec::new();
+/// An additional action declared by a desktop file (`[Desktop Action <id>]`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopAction {
+    /// Action ID from the `Actions=` key
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Icon name or path (falls back to the app's icon)
+    pub icon: Option<String>,
+    /// Command to run
+    pub exec: String,
+}
 
-    // System applications
-    dirs.push(PathBuf::from("/usr/share/applications"));
-    dirs.push(PathBuf::from("/usr/local/share/applications"));
+impl AppInfo {
+    /// Create a submenu item that opens a nested pie of `children`
+    pub fn submenu(name: &str, icon: Option<String>, children: Vec<AppInfo>) -> Self {
+        // Show running dots on the group if anything inside it is running
+        let running_count = children.iter().map(|c| c.running_count).sum();
+        Self {
+            id: format!("submenu:{}", name),
+            name: name.to_string(),
+            icon: Some(icon.unwrap_or_else(|| "folder".to_string())),
+            exec: None,
+            desktop_path: PathBuf::new(),
+            running_count,
+            is_favorite: true,
+            kind: ItemKind::Submenu(children),
+            actions: Vec::new(),
+            terminal: false,
+            working_dir: None,
+            keywords: Vec::new(),
+        }
+    }
 
-    // User applications
-    if let Some(data_dir) = dirs::data_local_dir() {
-        dirs.push(data_dir.join("applications"));
+    /// Create an item that runs a shell command
+    pub fn command(name: &str, exec: &str, icon: Option<String>) -> Self {
+        Self {
+            id: format!("command:{}", name),
+            name: name.to_string(),
+            icon: Some(icon.unwrap_or_else(|| "utilities-terminal".to_string())),
+            exec: Some(exec.to_string()),
+            desktop_path: PathBuf::new(),
+            running_count: 0,
+            is_favorite: true,
+            kind: ItemKind::Command,
+            actions: Vec::new(),
+            terminal: false,
+            working_dir: None,
+            keywords: Vec::new(),
+        }
     }
 
-    // Flatpak-installed applications
-    dirs.push(PathBuf::from("/var/lib/flatpak/exports/share/applications"));
-    if let Some(home) = dirs::home_dir() {
-        dirs.push(home.join(".local/share/flatpak/exports/share/applications"));
+    /// Create an item that opens a URL, file or folder
+    ///
+    /// Without a name the item is labelled with the file name or URL; without
+    /// an icon, one is picked by what the target is.
+    pub fn open(name: Option<String>, target: &str, icon: Option<String>) -> Self {
+        let target = expand_home(target);
+        let name = name.unwrap_or_else(|| {
+            if is_url(&target) {
+                target.clone()
+            } else {
+                Path::new(target.trim_start_matches("file://"))
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| target.clone())
+            }
+        });
+        let icon = icon.unwrap_or_else(|| {
+            if is_url(&target) {
+                "web-browser".to_string()
+            } else if Path::new(target.trim_start_matches("file://")).is_dir() {
+                "folder".to_string()
+            } else {
+                "text-x-generic".to_string()
+            }
+        });
+        Self {
+            id: format!("open:{}", target),
+            name,
+            icon: Some(icon),
+            exec: None,
+            desktop_path: PathBuf::new(),
+            running_count: 0,
+            is_favorite: true,
+            kind: ItemKind::Open(target),
+            actions: Vec::new(),
+            terminal: false,
+            working_dir: None,
+            keywords: Vec::new(),
+        }
     }
 
-    // Snap applications
-    dirs.push(PathBuf::from("/var/lib/snapd/desktop/applications"));
+    /// Create an item for a built-in action
+    pub fn builtin(action: BuiltinAction, name: Option<String>, icon: Option<String>) -> Self {
+        Self {
+            id: format!("builtin:{:?}", action),
+            name: name.unwrap_or_else(|| action.name().to_string()),
+            icon: Some(icon.unwrap_or_else(|| action.icon().to_string())),
+            exec: None,
+            desktop_path: PathBuf::new(),
+            running_count: 0,
+            is_favorite: true,
+            kind: ItemKind::Builtin(action),
+            actions: Vec::new(),
+            terminal: false,
+            working_dir: None,
+            keywords: Vec::new(),
+        }
+    }
 
+    /// Create an item for one open window of `app`, labelled with its title
+    pub fn window(app: &AppInfo, window: WindowInfo) -> Self {
+        // The center label puts each word on its own line, so keep titles short
+        const MAX_TITLE_CHARS: usize = 32;
+        let name = if window.title.is_empty() {
+            app.name.clone()
+        } else if window.title.chars().count() > MAX_TITLE_CHARS {
+            let short: String = window.title.chars().take(MAX_TITLE_CHARS - 1).collect();
+            format!("{}…", short.trim_end())
+        } else {
+            window.title.clone()
+        };
+        Self {
+            id: window.app_id.clone(),
+            name,
+            icon: app.icon.clone(),
+            exec: app.exec.clone(),
+            desktop_path: app.desktop_path.clone(),
+            running_count: 0,
+            is_favorite: false,
+            kind: ItemKind::Window(window),
+            actions: app.actions.clone(),
+            terminal: app.terminal,
+            working_dir: app.working_dir.clone(),
+            keywords: app.keywords.clone(),
+        }
+    }
+
+    /// Create an item that performs a management action on a window
+    pub fn window_action(window: &WindowInfo, action: WindowAction, name: &str, icon: &str) -> Self {
+        Self {
+            id: format!("window-action:{}:{:?}", window.app_id, action),
+            name: name.to_string(),
+            icon: Some(icon.to_string()),
+            exec: None,
+            desktop_path: PathBuf::new(),
+            running_count: 0,
+            is_favorite: false,
+            kind: ItemKind::WindowAction(window.clone(), action),
+            actions: Vec::new(),
+            terminal: false,
+            working_dir: None,
+            keywords: Vec::new(),
+        }
+    }
+
+    /// Create an item that runs one of `app`'s desktop actions
+    pub fn desktop_action(app: &AppInfo, action: &DesktopAction) -> Self {
+        Self {
+            id: app.id.clone(),
+            name: action.name.clone(),
+            icon: action.icon.clone().or_else(|| app.icon.clone()),
+            exec: Some(action.exec.clone()),
+            desktop_path: app.desktop_path.clone(),
+            running_count: 0,
+            is_favorite: false,
+            kind: ItemKind::App,
+            actions: Vec::new(),
+            terminal: app.terminal,
+            working_dir: app.working_dir.clone(),
+            keywords: Vec::new(),
+        }
+    }
+
+    /// Create an empty separator slice
+    pub fn separator() -> Self {
+        Self {
+            id: "separator".to_string(),
+            name: String::new(),
+            icon: None,
+            exec: None,
+            desktop_path: PathBuf::new(),
+            running_count: 0,
+            is_favorite: false,
+            kind: ItemKind::Separator,
+            actions: Vec::new(),
+            terminal: false,
+            working_dir: None,
+            keywords: Vec::new(),
+        }
+    }
+
+    /// Whether selecting this item opens a nested pie
+    pub fn is_submenu(&self) -> bool {
+        matches!(self.kind, ItemKind::Submenu(_))
+    }
+
+    /// Whether this item is an empty gap in the ring
+    pub fn is_separator(&self) -> bool {
+        matches!(self.kind, ItemKind::Separator)
+    }
+
+    /// Whether every word of a search query appears in the name, ID or
+    /// keywords, ignoring case
+    pub fn matches_query(&self, query: &str) -> bool {
+        let name = self.name.to_lowercase();
+        let id = self.id.to_lowercase();
+        let keywords: Vec<String> = self.keywords.iter().map(|k| k.to_lowercase()).collect();
+        query.to_lowercase().split_whitespace().all(|word| {
+            name.contains(word) || id.contains(word) || keywords.iter().any(|k| k.contains(word))
+        })
+    }
+
+    /// Program and arguments that launch this app
+    ///
+    /// Expands the Exec field codes and wraps terminal apps in the user's
+    /// terminal. Returns `None` if there is no usable Exec.
+    pub fn launch_args(&self) -> Option<Vec<String>> {
+        let args = parse_exec(self.exec.as_deref()?, &self.name, self.icon.as_deref(), &self.desktop_path)?;
+        if !self.terminal {
+            return Some(args);
+        }
+        let mut command = terminal_command();
+        command.extend(args);
+        Some(command)
+    }
+}
+
+/// Get all standard locations for desktop files, in lookup priority order
+///
+/// Follows the XDG base directory spec: `$XDG_DATA_HOME` first, so user
+/// entries override system entries with the same ID, then each of
+/// `$XDG_DATA_DIRS` in order.
+pub fn desktop_file_dirs() -> Vec<PathBuf> {
+    match xdg::BaseDirectories::new() {
+        Ok(base) => application_dirs(base.get_data_home(), base.get_data_dirs()),
+        Err(e) => {
+            eprintln!("Failed to read XDG base directories: {}", e);
+            application_dirs(
+                dirs::data_local_dir().unwrap_or_default(),
+                vec![PathBuf::from("/usr/local/share"), PathBuf::from("/usr/share")],
+            )
+        }
+    }
+}
+
+/// Build the list of `applications` directories from XDG data directories
+fn application_dirs(data_home: PathBuf, data_dirs: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let candidates = std::iter::once(data_home)
+        .chain(data_dirs)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join("applications"));
+
+    // Flatpak and Snap normally add their exports to XDG_DATA_DIRS from a login
+    // script; keep them as a fallback for sessions that skip it
+    let mut fallbacks = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        fallbacks.push(home.join(".local/share/flatpak/exports/share/applications"));
+    }
+    fallbacks.push(PathBuf::from("/var/lib/flatpak/exports/share/applications"));
+    fallbacks.push(PathBuf::from("/var/lib/snapd/desktop/applications"));
+
+    for dir in candidates.chain(fallbacks) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
     dirs
 }
 
-/// Find the desktop file for an app ID
-fn find_desktop_file(app_id: &str) -> Option<PathBuf> {
-    let filename = format!("{}.desktop", app_id);
+/// Fields read from a desktop file
+struct DesktopEntry {
+    name: String,
+    icon: Option<String>,
+    exec: Option<String>,
+    terminal: bool,
+    working_dir: Option<PathBuf>,
+    keywords: Vec<String>,
+    /// Hidden from app menus (`NoDisplay=true`), though windows may still match it
+    no_display: bool,
+    actions: Vec<DesktopAction>,
+}
 
-    // First, try exact match
-    for dir in desktop_file_dirs() {
-        let path = dir.join(&filename);
-        if path.exists() {
-            return Some(path);
+/// Locale keys to try for localized values, most specific first
+///
+/// Reads the messages locale from the environment the way the Desktop Entry
+/// Spec describes: `de_DE.UTF-8@euro` gives `de_DE@euro`, `de_DE`, `de@euro`
+/// and `de`.
+fn current_locales() -> Vec<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    locale_candidates(&locale)
+}
+
+/// Expand a POSIX locale name into the keys it matches, most specific first
+fn locale_candidates(locale: &str) -> Vec<String> {
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    // The encoding never takes part in matching
+    let rest = rest.split('.').next().unwrap_or_default();
+    if rest.is_empty() || rest == "C" || rest == "POSIX" {
+        return Vec::new();
+    }
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+
+    let mut candidates = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{}@{}", lang, modifier));
+    }
+    candidates.push(lang.to_string());
+    candidates
+}
+
+/// A localizable value, keeping whichever line best matches the locale
+#[derive(Default)]
+struct Localized {
+    value: Option<String>,
+    /// Index into the locale list (the list length for the unlocalized key)
+    rank: usize,
+}
+
+impl Localized {
+    fn offer(&mut self, locale: Option<&str>, value: String, locales: &[String]) {
+        let rank = match locale {
+            None => locales.len(),
+            Some(locale) => match locales.iter().position(|l| l == locale) {
+                Some(rank) => rank,
+                None => return,
+            },
+        };
+        // The first line wins between equally good matches
+        if self.value.is_none() || rank < self.rank {
+            self.value = Some(value);
+            self.rank = rank;
         }
     }
+}
 
-    // If no exact match, search for desktop files ending with the app_id
-    // This handles cases like app_id="Slack" matching "com.slack.Slack.desktop"
-    let app_id_lower = app_id.to_lowercase();
-    for dir in desktop_file_dirs() {
-        if let Ok(entries) = fs::read_dir(&dir) {
-            for entry in entries.filter_map(|e| e.ok()) {
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
-                if name_str.ends_with(".desktop") {
-                    // Check if the last component before .desktop matches
-                    let base = name_str.trim_end_matches(".desktop");
-                    if let Some(last_part) = base.rsplit('.').next() {
-                        if last_part.to_lowercase() == app_id_lower {
-                            return Some(entry.path());
-                        }
-                    }
-                    // Also try case-insensitive full match
-                    if base.to_lowercase() == app_id_lower {
-                        return Some(entry.path());
-                    }
-                }
+/// Undo the escapes allowed in desktop file string values (`\s`, `\n`, ...)
+fn unescape_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
             }
+            None => out.push('\\'),
         }
     }
+    out
+}
 
-    None
+/// One `Key[locale]=value` line of a desktop file
+pub struct DesktopLine<'a> {
+    /// Name of the group the line is in, without brackets (`Desktop Entry`)
+    pub group: &'a str,
+    pub key: &'a str,
+    /// Locale of a localized key (`de_DE` in `Name[de_DE]`)
+    pub locale: Option<&'a str>,
+    /// The value with its escapes undone
+    pub value: String,
+}
+
+/// Split the contents of a desktop file into its key-value lines
+///
+/// The one parser for desktop files: the app loader and the desktop file
+/// index both read their fields from this. Comments, blank lines and lines
+/// before the first group are skipped.
+pub fn desktop_lines(content: &str) -> Vec<DesktopLine<'_>> {
+    let mut lines = Vec::new();
+    let mut group = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = Some(name);
+            continue;
+        }
+        let (Some(group), Some((key, value))) = (group, line.split_once('=')) else {
+            continue;
+        };
+        // Split `Name[de_DE]` into the key and its locale
+        let (key, locale) = match key.trim().split_once('[') {
+            Some((key, locale)) => (key, locale.strip_suffix(']')),
+            None => (key.trim(), None),
+        };
+        lines.push(DesktopLine {
+            group,
+            key,
+            locale,
+            value: unescape_value(value.trim()),
+        });
+    }
+    lines
+}
+
+/// Split a `;`-separated desktop file list, dropping empty items
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Check whether a `TryExec` program is installed
+fn program_exists(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
 }
 
-/// Parse a simple desktop file to extract key fields
-/// This is a basic parser - for complex cases use freedesktop-desktop-entry crate
-fn parse_desktop_file(path: &Path) -> Option<(String, Option<String>, Option<String>)> {
+/// Parse a desktop file
+fn parse_desktop_file(path: &Path) -> Option<DesktopEntry> {
     let content = fs::read_to_string(path).ok()?;
-    let mut name = None;
+    parse_desktop_entry(&content, &current_locales())
+}
+
+/// Parse the contents of a desktop file
+///
+/// Reads `[Desktop Entry]` plus the `[Desktop Action <id>]` groups it lists in
+/// `Actions=`, in that order. Names are localized using `locales` (see
+/// [`current_locales`]). Actions without a name or exec are skipped.
+///
+/// Returns `None` for entries that are `Hidden` (deleted) or whose `TryExec`
+/// program isn't installed. `NoDisplay` entries are kept: that only hides them
+/// from app menus, while the pie shows apps that are pinned or running.
+fn parse_desktop_entry(content: &str, locales: &[String]) -> Option<DesktopEntry> {
+    let mut name = Localized::default();
     let mut icon = None;
     let mut exec = None;
-    let mut in_desktop_entry = false;
+    let mut terminal = false;
+    let mut working_dir = None;
+    let mut keywords = Localized::default();
+    let mut no_display = false;
+    let mut action_ids: Vec<String> = Vec::new();
+    let mut found_actions: HashMap<String, PartialAction> = HashMap::new();
 
-    for line in content.lines() {
-        let line = line.trim();
+    /// A `[Desktop Action <id>]` group read so far
+    #[derive(Default)]
+    struct PartialAction {
+        name: Localized,
+        icon: Option<String>,
+        exec: Option<String>,
+    }
 
-        if line == "[Desktop Entry]" {
-            in_desktop_entry = true;
-            continue;
+    for DesktopLine { group, key, locale, value } in desktop_lines(content) {
+        if group == "Desktop Entry" {
+            match (key, locale) {
+                ("Name", _) => name.offer(locale, value, locales),
+                ("Icon", None) => icon = Some(value),
+                ("Exec", None) => exec = Some(value),
+                ("Terminal", None) => terminal = value == "true",
+                ("Path", None) if !value.is_empty() => working_dir = Some(PathBuf::from(value)),
+                ("Keywords", _) => keywords.offer(locale, value, locales),
+                ("NoDisplay", None) => no_display = value == "true",
+                ("Hidden", None) if value == "true" => return None,
+                ("TryExec", None) if !program_exists(&value) => return None,
+                ("Actions", None) => action_ids = split_list(&value),
+                _ => {}
+            }
+        } else if let Some(id) = group.strip_prefix("Desktop Action ") {
+            let action = found_actions.entry(id.to_string()).or_default();
+            match (key, locale) {
+                ("Name", _) => action.name.offer(locale, value, locales),
+                ("Icon", None) => action.icon = Some(value),
+                ("Exec", None) => action.exec = Some(value),
+                _ => {}
+            }
         }
+    }
 
-        if line.starts_with('[') {
-            in_desktop_entry = false;
-            continue;
+    // Only actions listed in Actions= count, in the order listed there
+    let actions = action_ids
+        .into_iter()
+        .filter_map(|id| {
+            let action = found_actions.remove(&id)?;
+            Some(DesktopAction {
+                id,
+                name: action.name.value?,
+                icon: action.icon,
+                exec: action.exec.filter(|e| !e.trim().is_empty())?,
+            })
+        })
+        .collect();
+
+    Some(DesktopEntry {
+        name: name.value?,
+        icon,
+        exec,
+        terminal,
+        working_dir,
+        keywords: keywords
+            .value
+            .map(|value| split_list(&value))
+            .unwrap_or_default(),
+        no_display,
+        actions,
+    })
+}
+
+/// Split an Exec value into arguments and expand its field codes
+///
+/// Follows the quoting rules of the Desktop Entry Spec. The pie never passes
+/// files or URLs, so `%f`, `%u` and friends expand to nothing, as do the
+/// deprecated codes. `%i`, `%c` and `%k` expand to the icon, name and desktop
+/// file path. Returns `None` for an empty command or an unterminated quote.
+pub fn parse_exec(exec: &str, name: &str, icon: Option<&str>, desktop_path: &Path) -> Option<Vec<String>> {
+    let tokens = split_exec(exec)?;
+
+    let mut args = Vec::new();
+    for (token, quoted) in tokens {
+        match (quoted, token.as_str()) {
+            (false, "%i") => {
+                if let Some(icon) = icon {
+                    args.push("--icon".to_string());
+                    args.push(icon.to_string());
+                }
+            }
+            // Codes that stand alone expand to no argument at all
+            (false, "%f" | "%F" | "%u" | "%U" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m") => {}
+            _ => args.push(expand_field_codes(&token, name, desktop_path)),
+        }
+    }
+
+    if args.is_empty() {
+        None
+    } else {
+        Some(args)
+    }
+}
+
+/// Split an Exec value into (argument, was quoted) pairs
+///
+/// Follows the quoting rules of the Desktop Entry Spec without expanding
+/// field codes. Returns `None` for an unterminated quote.
+pub fn split_exec(exec: &str) -> Option<Vec<(String, bool)>> {
+    let mut tokens: Vec<(String, bool)> = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_token = true;
+                quoted = true;
+            }
+            '\\' if in_quotes => match chars.next() {
+                Some(escaped @ ('"' | '`' | '$' | '\\')) => current.push(escaped),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => return None,
+            },
+            ' ' | '\t' if !in_quotes => {
+                if in_token {
+                    tokens.push((std::mem::take(&mut current), quoted));
+                    in_token = false;
+                    quoted = false;
+                }
+            }
+            _ => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    if in_token {
+        tokens.push((current, quoted));
+    }
+    Some(tokens)
+}
+
+/// Quote an argument so [`split_exec`] reads it back unchanged
+pub fn quote_exec_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"', '\\']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
         }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
 
-        if !in_desktop_entry {
+/// Expand field codes embedded in a single Exec argument
+fn expand_field_codes(arg: &str, name: &str, desktop_path: &Path) -> String {
+    let mut out = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
             continue;
         }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('c') => out.push_str(name),
+            Some('k') => out.push_str(&desktop_path.to_string_lossy()),
+            // File, URL, icon and deprecated codes expand to nothing
+            _ => {}
+        }
+    }
+    out
+}
 
-        if let Some(value) = line.strip_prefix("Name=") {
-            if name.is_none() {
-                name = Some(value.to_string());
-            }
-        } else if let Some(value) = line.strip_prefix("Icon=") {
-            icon = Some(value.to_string());
-        } else if let Some(value) = line.strip_prefix("Exec=") {
-            // Remove field codes like %u, %f, etc.
-            let cleaned = value
-                .replace("%u", "")
-                .replace("%U", "")
-                .replace("%f", "")
-                .replace("%F", "")
-                .replace("%i", "")
-                .replace("%c", "")
-                .replace("%k", "")
-                .trim()
-                .to_string();
-            exec = Some(cleaned);
+/// Command prefix that runs a program in the user's terminal
+///
+/// Uses `$TERMINAL` if set, else COSMIC Terminal, with the `-e` convention.
+fn terminal_command() -> Vec<String> {
+    let mut command: Vec<String> = std::env::var("TERMINAL")
+        .ok()
+        .map(|t| t.split_whitespace().map(String::from).collect())
+        .filter(|t: &Vec<String>| !t.is_empty())
+        .unwrap_or_else(|| vec!["cosmic-term".to_string()]);
+    command.push("-e".to_string());
+    command
+}
+
+/// User-configured aliases from window app_ids to desktop IDs
+///
+/// Cached, and re-read when the config file changes (the pie service lives for
+/// the whole session).
+fn app_aliases() -> Arc<BTreeMap<String, String>> {
+    type Cached = (Option<SystemTime>, Arc<BTreeMap<String, String>>);
+    static ALIASES: Mutex<Option<Cached>> = Mutex::new(None);
+
+    let modified = fs::metadata(PieMenuConfig::config_path())
+        .and_then(|m| m.modified())
+        .ok();
+    let mut cached = ALIASES.lock().unwrap_or_else(|e| e.into_inner());
+    match cached.as_ref() {
+        Some((time, aliases)) if *time == modified => Arc::clone(aliases),
+        _ => {
+            let aliases = Arc::new(PieMenuConfig::load().app_aliases);
+            *cached = Some((modified, Arc::clone(&aliases)));
+            aliases
         }
     }
+}
 
-    Some((name?, icon, exec))
+/// Get the desktop ID a window's app_id belongs to
+///
+/// Checks the configured aliases, then the desktop file index (desktop IDs,
+/// `StartupWMClass` and Flatpak IDs).
+pub fn resolve_desktop_id(app_id: &str) -> Option<String> {
+    match app_aliases().get(app_id) {
+        Some(alias) => Some(alias.clone()),
+        None => desktop_index::resolve_app_id(app_id),
+    }
 }
 
 /// Load information for a single app by ID
+///
+/// The ID may be a desktop ID or a window app_id; aliases are applied first.
 pub fn load_app_info(app_id: &str) -> Option<AppInfo> {
-    let desktop_path = find_desktop_file(app_id)?;
-    let (name, icon, exec) = parse_desktop_file(&desktop_path)?;
+    let aliases = app_aliases();
+    let desktop_id = aliases.get(app_id).map_or(app_id, String::as_str);
+    let desktop_path = desktop_index::find_desktop_file(desktop_id)?;
+    let entry = parse_desktop_file(&desktop_path)?;
 
     Some(AppInfo {
         id: app_id.to_string(),
-        name,
-        icon,
-        exec,
+        name: entry.name,
+        icon: entry.icon,
+        exec: entry.exec,
         desktop_path,
         running_count: 0,
         is_favorite: false,
+        kind: ItemKind::App,
+        actions: entry.actions,
+        terminal: entry.terminal,
+        working_dir: entry.working_dir,
+        keywords: entry.keywords,
     })
 }
 
+/// Build one item per open window, with each window's app name and icon
+///
+/// Windows whose app has no desktop file use the app ID as name and icon.
+pub fn load_window_items(windows: Vec<WindowInfo>) -> Vec<AppInfo> {
+    let mut app_cache: HashMap<String, AppInfo> = HashMap::new();
+
+    windows
+        .into_iter()
+        .map(|window| {
+            let app = app_cache.entry(window.app_id.clone()).or_insert_with(|| {
+                load_app_info(&window.app_id).unwrap_or_else(|| AppInfo {
+                    id: window.app_id.clone(),
+                    name: window.app_id.clone(),
+                    icon: Some(window.app_id.clone()),
+                    exec: None,
+                    desktop_path: PathBuf::new(),
+                    running_count: 0,
+                    is_favorite: false,
+                    kind: ItemKind::App,
+                    actions: Vec::new(),
+                    terminal: false,
+                    working_dir: None,
+                    keywords: Vec::new(),
+                })
+            });
+            AppInfo::window(app, window)
+        })
+        .collect()
+}
+
+/// Load every installed app that app menus show, sorted by name
+///
+/// Used when a search finds nothing in the pie itself.
+pub fn load_installed_apps() -> Vec<AppInfo> {
+    let mut apps: Vec<AppInfo> = desktop_index::all_desktop_files()
+        .into_iter()
+        .filter_map(|desktop_path| {
+            let id = desktop_path.file_stem()?.to_str()?.to_string();
+            let entry = parse_desktop_file(&desktop_path).filter(|e| !e.no_display && e.exec.is_some())?;
+            Some(AppInfo {
+                id,
+                name: entry.name,
+                icon: entry.icon,
+                exec: entry.exec,
+                desktop_path,
+                running_count: 0,
+                is_favorite: false,
+                kind: ItemKind::App,
+                actions: entry.actions,
+                terminal: entry.terminal,
+                working_dir: entry.working_dir,
+                keywords: entry.keywords,
+            })
+        })
+        .collect();
+    apps.sort_by_key(|app| app.name.to_lowercase());
+    apps
+}
+
 /// Load information for multiple apps (favorites)
 pub fn load_apps(app_ids: &[String]) -> Vec<AppInfo> {
     app_ids
@@ -172,70 +917,204 @@ pub fn load_apps(app_ids: &[String]) -> Vec<AppInfo> {
 }
 
 /// Load apps with running status
-/// Returns favorites first, then running non-favorites
-pub fn load_apps_with_running(favorites: &[String], running_apps: &HashMap<String, u32>) -> Vec<AppInfo> {
+/// Returns favorites first, then running non-favorites placed by `placement`
+pub fn load_apps_with_running(
+    favorites: &[String],
+    running_apps: &HashMap<String, u32>,
+    placement: RunningPlacement,
+) -> Vec<AppInfo> {
     let mut apps = Vec::new();
+    let mut others = Vec::new();
     let mut seen_ids = HashSet::new();
+    let running = resolve_running(running_apps);
 
     // First, add all favorites and mark if running
     for id in favorites {
         if let Some(mut app) = load_app_info(id) {
             app.is_favorite = true;
-            app.running_count = get_running_count(id, running_apps);
-            seen_ids.insert(id.clone());
+            app.running_count = get_running_count(id, &running);
+            seen_ids.insert(id.as_str());
             apps.push(app);
         }
     }
 
-    // Then, add running apps that aren't favorites
-    for (running_id, count) in running_apps {
-        if !seen_ids.contains(running_id) && !is_id_in_set(running_id, &seen_ids) {
-            if let Some(mut app) = load_app_info(running_id) {
+    // Then, add running apps that aren't favorites (sorted, so the same
+    // app wins every time when several running IDs match it)
+    for (running_id, count) in &running {
+        if !seen_ids.iter().any(|id| running_id.belongs_to(id)) {
+            if let Some(mut app) = load_app_info(running_id.id) {
                 app.is_favorite = false;
                 app.running_count = *count;
-                seen_ids.insert(running_id.clone());
-                apps.push(app);
+                seen_ids.insert(running_id.id);
+                others.push(app);
             }
         }
     }
 
+    // Sorting by name needs no history; the other policies remember first-seen times
+    let order = if placement == RunningPlacement::Alphabetical {
+        RunningOrder::default()
+    } else {
+        let mut order = RunningOrder::load();
+        if order.update(others.iter().map(|app| app.id.as_str()), history::now()) {
+            if let Err(e) = order.save() {
+                eprintln!("Failed to save running app order: {}", e);
+            }
+        }
+        order
+    };
+    place_running(&mut others, placement, &order);
+
+    if placement == RunningPlacement::Separate && !apps.is_empty() && !others.is_empty() {
+        apps.push(AppInfo::separator());
+    }
+    apps.extend(others);
     apps
 }
 
-/// Get the running window count for an app ID (case-insensitive, handles variations)
-fn get_running_count(app_id: &str, running_apps: &HashMap<String, u32>) -> u32 {
-    // Direct match
-    if let Some(&count) = running_apps.get(app_id) {
-        return count;
+/// Sort running non-favorites so each keeps its place between opens
+fn place_running(apps: &mut [AppInfo], placement: RunningPlacement, order: &RunningOrder) {
+    match placement {
+        RunningPlacement::Alphabetical => apps.sort_by_cached_key(|app| (app.name.to_lowercase(), app.id.clone())),
+        RunningPlacement::FirstSeen | RunningPlacement::Separate => {
+            apps.sort_by_cached_key(|app| (order.first_seen(&app.id), app.id.clone()))
+        }
     }
+}
 
-    let app_id_lower = app_id.to_lowercase();
-    for (running, &count) in running_apps {
-        // Case-insensitive match
-        if running.to_lowercase() == app_id_lower {
-            return count;
-        }
-        // Match the last part after dots (e.g., org.gnome.Nautilus -> Nautilus)
-        if let Some(name) = running.rsplit('.').next() {
-            if name.to_lowercase() == app_id_lower {
-                return count;
-            }
-        }
-        // Reverse: if app_id has dots, match its last part
-        if let Some(name) = app_id.rsplit('.').next() {
-            if running.to_lowercase() == name.to_lowercase() {
-                return count;
-            }
+/// Launches needed before an app not in the ring is suggested
+const MIN_SUGGESTED_LAUNCHES: u32 = 3;
+
+/// Frecency of an item: its launch count weighted by how recently it was
+/// last launched, so an app used every day outranks one used a lot last month
+pub fn frecency(record: &LaunchRecord, now: u64) -> f64 {
+    const HOUR: u64 = 60 * 60;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    let weight = match now.saturating_sub(record.last_used) {
+        age if age < HOUR => 4.0,
+        age if age < DAY => 2.0,
+        age if age < WEEK => 0.5,
+        _ => 0.25,
+    };
+    record.count as f64 * weight
+}
+
+/// Sort dock items by frecency, most used first, so they take the first
+/// slices (top of the ring, and the 1-9 keys)
+///
+/// Only favorites move: running apps and separators keep their slots, so the
+/// running placement policy still holds. The sort is stable: items that were
+/// never launched keep their order.
+pub fn sort_by_frecency(apps: &mut [AppInfo], history: &LaunchHistory, now: u64) {
+    let score = |app: &AppInfo| history.get(&app.id).map_or(0.0, |record| frecency(record, now));
+    let slots: Vec<usize> = (0..apps.len()).filter(|&i| apps[i].is_favorite).collect();
+    let mut favorites: Vec<AppInfo> = slots.iter().map(|&i| apps[i].clone()).collect();
+    favorites.sort_by(|a, b| score(b).total_cmp(&score(a)));
+    for (i, app) in slots.into_iter().zip(favorites) {
+        apps[i] = app;
+    }
+}
+
+/// Apps launched often that aren't already in the ring, most frecent first
+///
+/// Only installed apps are suggested, at most `count` of them.
+pub fn suggested_apps(history: &LaunchHistory, ring: &[AppInfo], count: usize, now: u64) -> Vec<AppInfo> {
+    let ring_ids: Vec<ResolvedId> = ring.iter().map(|app| ResolvedId::new(&app.id)).collect();
+    let mut candidates: Vec<(&String, f64)> = history
+        .items
+        .iter()
+        .filter(|(_, record)| record.count >= MIN_SUGGESTED_LAUNCHES)
+        .filter(|(id, _)| {
+            let id = ResolvedId::new(id);
+            !ring_ids.iter().any(|app| id.belongs_to(app.id) || app.belongs_to(id.id))
+        })
+        .map(|(id, record)| (id, frecency(record, now)))
+        .collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    candidates
+        .into_iter()
+        .filter_map(|(id, _)| load_app_info(id))
+        .take(count)
+        .collect()
+}
+
+/// A window app_id with the desktop ID it resolves to, looked up once so it
+/// can be matched against many apps
+pub struct ResolvedId<'a> {
+    pub id: &'a str,
+    desktop_id: Option<String>,
+}
+
+impl<'a> ResolvedId<'a> {
+    pub fn new(id: &'a str) -> Self {
+        Self {
+            id,
+            desktop_id: resolve_desktop_id(id),
         }
     }
 
-    0
+    /// Check whether this ID belongs to an app (see [`app_id_matches`])
+    pub fn belongs_to(&self, app_id: &str) -> bool {
+        self.desktop_id
+            .as_deref()
+            .is_some_and(|desktop_id| desktop_id.eq_ignore_ascii_case(app_id))
+            || id_heuristics_match(app_id, self.id)
+    }
 }
 
-/// Check if an ID is already in the seen set (handles case variations)
-fn is_id_in_set(id: &str, seen: &HashSet<String>) -> bool {
-    let id_lower = id.to_lowercase();
-    seen.iter().any(|s| s.to_lowercase() == id_lower)
+/// Resolve every running app_id once, sorted by app_id, with its window count
+pub fn resolve_running(running_apps: &HashMap<String, u32>) -> Vec<(ResolvedId<'_>, u32)> {
+    let mut running: Vec<_> = running_apps
+        .iter()
+        .map(|(id, &count)| (ResolvedId::new(id), count))
+        .collect();
+    running.sort_by(|a, b| a.0.id.cmp(b.0.id));
+    running
+}
+
+/// Get the running window count for an app ID
+///
+/// Adds up every running app_id that belongs to the app (see [`app_id_matches`]).
+pub fn get_running_count(app_id: &str, running: &[(ResolvedId, u32)]) -> u32 {
+    running
+        .iter()
+        .filter(|(running_id, _)| running_id.belongs_to(app_id))
+        .map(|(_, count)| count)
+        .sum()
+}
+
+/// Check whether a running window's app ID belongs to an app
+///
+/// Matches when the window's app_id resolves to the app's desktop ID (through
+/// an alias, `StartupWMClass` or Flatpak ID), so PWAs and Steam games find
+/// their entries. Also handles case differences and reverse-DNS IDs on either
+/// side (e.g. `org.gnome.Nautilus` matches `nautilus`). To match one ID
+/// against many apps, resolve it once with [`ResolvedId`].
+pub fn app_id_matches(app_id: &str, running: &str) -> bool {
+    ResolvedId::new(running).belongs_to(app_id)
+}
+
+/// Match IDs by case-insensitive comparison and their last dotted part
+fn id_heuristics_match(app_id: &str, running: &str) -> bool {
+    let app_id_lower = app_id.to_lowercase();
+    // Case-insensitive match
+    if running.to_lowercase() == app_id_lower {
+        return true;
+    }
+    // Match the last part after dots (e.g., org.gnome.Nautilus -> Nautilus)
+    if let Some(name) = running.rsplit('.').next() {
+        if name.to_lowercase() == app_id_lower {
+            return true;
+        }
+    }
+    // Reverse: if app_id has dots, match its last part
+    if let Some(name) = app_id.rsplit('.').next() {
+        if running.to_lowercase() == name.to_lowercase() {
+            return true;
+        }
+    }
+    false
 }
 
 /// Dock applet definition
@@ -282,6 +1161,11 @@ pub fn load_dock_applets(enabled_applets: &[String]) -> Vec<AppInfo> {
                 desktop_path: PathBuf::new(), // No desktop file for applets
                 running_count: 0,
                 is_favorite: true, // Treat as favorites since they're in the dock
+                kind: ItemKind::App,
+                actions: Vec::new(),
+                terminal: false,
+                working_dir: None,
+                keywords: Vec::new(),
             });
         }
     }
@@ -289,6 +1173,57 @@ pub fn load_dock_applets(enabled_applets: &[String]) -> Vec<AppInfo> {
     apps
 }
 
+/// Fold apps listed in configured groups into submenu items
+///
+/// Each group takes the ring position of its first member, and its children are
+/// ordered as listed in the group. Apps not in any group keep their position.
+/// Groups whose apps aren't in the ring are dropped.
+pub fn apply_groups(apps: Vec<AppInfo>, groups: &[PieGroup]) -> Vec<AppInfo> {
+    if groups.is_empty() {
+        return apps;
+    }
+
+    /// A ring position: either a plain app or the placeholder for a group
+    enum Slot {
+        App(Box<AppInfo>),
+        Group(usize),
+    }
+
+    let mut members: Vec<Vec<AppInfo>> = vec![Vec::new(); groups.len()];
+    let mut slots = Vec::new();
+
+    for app in apps {
+        // First matching group wins if an app is listed twice
+        let group = groups.iter().position(|g| {
+            g.apps.iter().any(|id| id.eq_ignore_ascii_case(&app.id))
+        });
+        match group {
+            Some(g) => {
+                if members[g].is_empty() {
+                    slots.push(Slot::Group(g));
+                }
+                members[g].push(app);
+            }
+            None => slots.push(Slot::App(Box::new(app))),
+        }
+    }
+
+    slots
+        .into_iter()
+        .map(|slot| match slot {
+            Slot::App(app) => *app,
+            Slot::Group(g) => {
+                let group = &groups[g];
+                let mut children = std::mem::take(&mut members[g]);
+                children.sort_by_key(|c| {
+                    group.apps.iter().position(|id| id.eq_ignore_ascii_case(&c.id))
+                });
+                AppInfo::submenu(&group.name, group.icon.clone(), children)
+            }
+        })
+        .collect()
+}
+
 /// Find icon path for an icon name
 /// Returns the path to the icon file, preferring SVG, then PNG
 pub fn find_icon_path(icon_name: &str, size: u16) -> Option<PathBuf> {
@@ -388,6 +1323,286 @@ mod tests {
         }
     }
 
+    fn test_app(id: &str) -> AppInfo {
+        AppInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            icon: None,
+            exec: None,
+            desktop_path: PathBuf::new(),
+            running_count: 0,
+            is_favorite: true,
+            kind: ItemKind::App,
+            actions: Vec::new(),
+            terminal: false,
+            working_dir: None,
+            keywords: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_apply_groups() {
+        let apps = vec![test_app("files"), test_app("firefox"), test_app("term"), test_app("brave")];
+        let groups = vec![PieGroup {
+            name: "Browsers".to_string(),
+            icon: None,
+            apps: vec!["brave".to_string(), "Firefox".to_string()],
+        }];
+
+        let result = apply_groups(apps, &groups);
+        let ids: Vec<&str> = result.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["files", "submenu:Browsers", "term"]);
+
+        // Children follow the order listed in the group
+        match &result[1].kind {
+            ItemKind::Submenu(children) => {
+                let child_ids: Vec<&str> = children.iter().map(|a| a.id.as_str()).collect();
+                assert_eq!(child_ids, ["brave", "firefox"]);
+            }
+            _ => panic!("expected a submenu"),
+        }
+    }
+
+    #[test]
+    fn test_parse_desktop_actions() {
+        let entry = parse_desktop_entry(
+            "[Desktop Entry]\n\
+             Name=Firefox\n\
+             Exec=firefox %u\n\
+             Actions=new-window;new-private-window;\n\
+             \n\
+             [Desktop Action new-private-window]\n\
+             Name=New Private Window\n\
+             Name[de]=Neues privates Fenster\n\
+             Exec=firefox --private-window %u\n\
+             \n\
+             [Desktop Action new-window]\n\
+             Name=New Window\n\
+             Exec=firefox --new-window %u\n\
+             \n\
+             [Desktop Action unlisted]\n\
+             Name=Not in Actions=\n\
+             Exec=firefox --unlisted\n",
+            &[],
+        )
+        .expect("valid entry");
+
+        assert_eq!(entry.name, "Firefox");
+        assert_eq!(entry.exec.as_deref(), Some("firefox %u"));
+        let actions: Vec<(&str, &str, &str)> = entry
+            .actions
+            .iter()
+            .map(|a| (a.id.as_str(), a.name.as_str(), a.exec.as_str()))
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                ("new-window", "New Window", "firefox --new-window %u"),
+                ("new-private-window", "New Private Window", "firefox --private-window %u"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_desktop_localized() {
+        let content = "[Desktop Entry]\n\
+                       Name=Files\n\
+                       Name[de]=Dateien\n\
+                       Name[de_AT]=Dateien (AT)\n\
+                       Terminal=true\n\
+                       Path=/tmp\n\
+                       Exec=files\n";
+
+        let locales = locale_candidates("de_DE.UTF-8");
+        assert_eq!(locales, ["de_DE", "de"]);
+        assert_eq!(parse_desktop_entry(content, &locales).unwrap().name, "Dateien");
+
+        let locales = locale_candidates("de_AT@euro");
+        assert_eq!(locales, ["de_AT@euro", "de_AT", "de@euro", "de"]);
+        assert_eq!(parse_desktop_entry(content, &locales).unwrap().name, "Dateien (AT)");
+
+        let entry = parse_desktop_entry(content, &locale_candidates("C")).unwrap();
+        assert_eq!(entry.name, "Files");
+        assert!(entry.terminal);
+        assert_eq!(entry.working_dir, Some(PathBuf::from("/tmp")));
+
+        // Hidden entries and missing TryExec programs count as not installed
+        assert!(parse_desktop_entry("[Desktop Entry]\nName=Gone\nHidden=true\n", &[]).is_none());
+        assert!(parse_desktop_entry(
+            "[Desktop Entry]\nName=Missing\nTryExec=/nonexistent/program\n",
+            &[]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_matches_query() {
+        let content = "[Desktop Entry]\n\
+                       Name=Files\n\
+                       Keywords=folder;manager;explore;\n\
+                       Keywords[de]=Ordner;Dateimanager;\n\
+                       NoDisplay=true\n\
+                       Exec=nautilus\n";
+        let entry = parse_desktop_entry(content, &locale_candidates("de_DE")).unwrap();
+        assert_eq!(entry.keywords, ["Ordner", "Dateimanager"]);
+        assert!(entry.no_display);
+
+        let mut app = test_app("org.gnome.Nautilus");
+        app.name = "Files".to_string();
+        app.keywords = parse_desktop_entry(content, &[]).unwrap().keywords;
+        assert!(app.matches_query("fil"));
+        assert!(app.matches_query("NAUT"));
+        assert!(app.matches_query("file manager"));
+        assert!(!app.matches_query("files browser"));
+    }
+
+    #[test]
+    fn test_id_heuristics_match() {
+        assert!(id_heuristics_match("org.gnome.Nautilus", "nautilus"));
+        assert!(id_heuristics_match("Slack", "com.slack.Slack"));
+        assert!(!id_heuristics_match("firefox", "thunderbird"));
+    }
+
+    #[test]
+    fn test_sort_by_frecency() {
+        let now = 100_000_000;
+        let mut history = LaunchHistory::default();
+        // Used a lot, but not this week
+        history.items.insert("gimp".to_string(), LaunchRecord { count: 10, last_used: now - 30 * 86400 });
+        // Used a little, just now
+        history.items.insert("firefox".to_string(), LaunchRecord { count: 2, last_used: now - 60 });
+        assert!(frecency(&history.items["firefox"], now) > frecency(&history.items["gimp"], now));
+
+        let mut apps: Vec<AppInfo> = ["files", "gimp", "term", "firefox"].into_iter().map(test_app).collect();
+        sort_by_frecency(&mut apps, &history, now);
+        let ids: Vec<&str> = apps.iter().map(|app| app.id.as_str()).collect();
+        assert_eq!(ids, ["firefox", "gimp", "files", "term"]);
+    }
+
+    #[test]
+    fn test_sort_by_frecency_keeps_running() {
+        let now = 100_000_000;
+        let mut history = LaunchHistory::default();
+        history.items.insert("gimp".to_string(), LaunchRecord { count: 5, last_used: now - 60 });
+        history.items.insert("zed".to_string(), LaunchRecord { count: 50, last_used: now - 60 });
+
+        // Laid out as load_apps_with_running does with Separate placement
+        let mut order = RunningOrder::default();
+        order.update(["zed"], 100);
+        order.update(["zed", "firefox"], 200);
+        let mut running: Vec<AppInfo> = ["firefox", "zed"].into_iter().map(test_app).collect();
+        for app in &mut running {
+            app.is_favorite = false;
+        }
+        place_running(&mut running, RunningPlacement::Separate, &order);
+        let mut apps: Vec<AppInfo> = ["files", "gimp"].into_iter().map(test_app).collect();
+        apps.push(AppInfo::separator());
+        apps.extend(running);
+
+        sort_by_frecency(&mut apps, &history, now);
+        let ids: Vec<&str> = apps.iter().map(|app| app.id.as_str()).collect();
+        assert_eq!(ids, ["gimp", "files", "separator", "zed", "firefox"]);
+    }
+
+    #[test]
+    fn test_place_running() {
+        let mut order = RunningOrder::default();
+        order.update(["zed", "gimp"], 100);
+        order.update(["zed", "gimp", "firefox"], 200);
+
+        let mut apps: Vec<AppInfo> = ["firefox", "zed", "gimp"].into_iter().map(test_app).collect();
+        apps[0].name = "Firefox".to_string();
+        apps[1].name = "Zed".to_string();
+        apps[2].name = "GIMP".to_string();
+
+        place_running(&mut apps, RunningPlacement::FirstSeen, &order);
+        let ids: Vec<&str> = apps.iter().map(|app| app.id.as_str()).collect();
+        assert_eq!(ids, ["gimp", "zed", "firefox"]);
+
+        place_running(&mut apps, RunningPlacement::Alphabetical, &order);
+        let ids: Vec<&str> = apps.iter().map(|app| app.id.as_str()).collect();
+        assert_eq!(ids, ["firefox", "gimp", "zed"]);
+    }
+
+    #[test]
+    fn test_application_dirs() {
+        let dirs = application_dirs(
+            PathBuf::from("/home/user/.local/share"),
+            vec![
+                PathBuf::from("/nix/profile/share"),
+                PathBuf::from("relative/share"),
+                PathBuf::from("/usr/share"),
+                PathBuf::from("/var/lib/flatpak/exports/share"),
+            ],
+        );
+        assert_eq!(
+            &dirs[..4],
+            [
+                PathBuf::from("/home/user/.local/share/applications"),
+                PathBuf::from("/nix/profile/share/applications"),
+                PathBuf::from("/usr/share/applications"),
+                PathBuf::from("/var/lib/flatpak/exports/share/applications"),
+            ]
+        );
+        // Fallbacks are only added once
+        let flatpak = PathBuf::from("/var/lib/flatpak/exports/share/applications");
+        assert_eq!(dirs.iter().filter(|d| **d == flatpak).count(), 1);
+        assert_eq!(dirs.last(), Some(&PathBuf::from("/var/lib/snapd/desktop/applications")));
+    }
+
+    #[test]
+    fn test_parse_exec() {
+        let path = Path::new("/usr/share/applications/app.desktop");
+        let parse = |exec: &str| parse_exec(exec, "My App", Some("app-icon"), path);
+
+        assert_eq!(parse("firefox %u").unwrap(), ["firefox"]);
+        assert_eq!(parse("app --name=%c %i").unwrap(), ["app", "--name=My App", "--icon", "app-icon"]);
+        assert_eq!(parse("app --file %k 100%%").unwrap(), ["app", "--file", path.to_str().unwrap(), "100%"]);
+        assert_eq!(
+            parse(r#""/opt/My App/run" --say "a \"quoted\" \$word" "" %F"#).unwrap(),
+            ["/opt/My App/run", "--say", "a \"quoted\" $word", ""]
+        );
+        // String-level escapes are undone before the Exec quoting rules apply
+        assert_eq!(
+            parse(&unescape_value(r#"sh -c "echo \\"hi\\"""#)).unwrap(),
+            ["sh", "-c", "echo \"hi\""]
+        );
+        assert!(parse("app \"unterminated").is_none());
+        assert!(parse("%U").is_none());
+    }
+
+    #[test]
+    fn test_open_item() {
+        let item = AppInfo::open(None, "https://example.com/dashboard", None);
+        assert_eq!(item.name, "https://example.com/dashboard");
+        assert_eq!(item.icon.as_deref(), Some("web-browser"));
+
+        let item = AppInfo::open(None, "/", None);
+        assert_eq!(item.icon.as_deref(), Some("folder"));
+
+        if let Some(home) = dirs::home_dir() {
+            let item = AppInfo::open(Some("Notes".to_string()), "~/notes.txt", None);
+            assert_eq!(item.name, "Notes");
+            match item.kind {
+                ItemKind::Open(target) => assert_eq!(target, format!("{}/notes.txt", home.display())),
+                _ => panic!("expected an open item"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_window_item() {
+        let app = test_app("firefox");
+        let window = WindowInfo {
+            identifier: "a1b2c3".to_string(),
+            app_id: "firefox".to_string(),
+            title: "A very long page title that keeps going and going".to_string(),
+        };
+        let item = AppInfo::window(&app, window.clone());
+        assert_eq!(item.name, "A very long page title that kee…");
+        assert!(matches!(item.kind, ItemKind::Window(w) if w == window));
+    }
+
     #[test]
     fn test_find_icon() {
         // Test COSMIC app icon
@@ -7,13 +7,16 @@
 //! - Reading COSMIC workspace layout to determine available swipe directions
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
+use crate::apps;
+
 /// Action to perform on a swipe gesture
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum SwipeAction {
     /// Do nothing (let system handle it)
     #[default]
@@ -26,31 +29,158 @@ pub enum SwipeAction {
     Workspaces,
     /// Open the pie menu
     PieMenu,
+    /// Run any program
+    Command {
+        /// Program to run (name on PATH or full path)
+        exec: String,
+        /// Arguments passed to the program
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Open a URL with the default handler
+    OpenUrl(String),
+    /// Launch an installed app by desktop ID
+    Desktop(String),
 }
 
 impl SwipeAction {
     /// Get the command to execute for this action
+    ///
+    /// Only the COSMIC overlays have one; they are toggled by repeated swipes.
+    /// Custom actions (`Command`, `OpenUrl`, `Desktop`) return `None` and are
+    /// launched fresh on every swipe.
     pub fn command(&self) -> Option<&'static str> {
         match self {
-            Self::None => None,
             Self::AppLibrary => Some("cosmic-app-library"),
             Self::Launcher => Some("cosmic-launcher"),
             Self::Workspaces => Some("cosmic-workspaces"),
-            Self::PieMenu => None, // Handled specially
+            Self::None | Self::PieMenu => None, // PieMenu is handled specially
+            Self::Command { .. } | Self::OpenUrl(_) | Self::Desktop(_) => None,
         }
     }
 
     /// All available actions for UI display
+    ///
+    /// Custom actions are listed with an empty target, to be filled in with
+    /// [`SwipeAction::with_target`].
     pub fn all() -> &'static [SwipeAction] {
-        &[
-            Self::None,
-            Self::AppLibrary,
-            Self::Launcher,
-            Self::Workspaces,
-            Self::PieMenu,
-        ]
+        const ALL: &[SwipeAction] = &[
+            SwipeAction::None,
+            SwipeAction::AppLibrary,
+            SwipeAction::Launcher,
+            SwipeAction::Workspaces,
+            SwipeAction::PieMenu,
+            SwipeAction::Command {
+                exec: String::new(),
+                args: Vec::new(),
+            },
+            SwipeAction::OpenUrl(String::new()),
+            SwipeAction::Desktop(String::new()),
+        ];
+        ALL
+    }
+
+    /// Check whether two actions are the same kind, ignoring their targets
+    pub fn same_kind(&self, other: &SwipeAction) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    /// The user-editable target of a custom action
+    ///
+    /// For `Command` this is the program followed by its arguments, separated
+    /// by spaces. Returns `None` for built-in actions.
+    pub fn target(&self) -> Option<String> {
+        match self {
+            Self::Command { exec, args } => Some(
+                std::iter::once(exec)
+                    .chain(args)
+                    .map(|arg| apps::quote_exec_arg(arg))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            Self::OpenUrl(url) => Some(url.clone()),
+            Self::Desktop(app_id) => Some(app_id.clone()),
+            _ => None,
+        }
     }
 
+    /// Replace the target of a custom action (see [`SwipeAction::target`])
+    ///
+    /// Built-in actions are returned unchanged. Command targets are split with
+    /// the Exec quoting rules; while a quote is still open, on whitespace.
+    pub fn with_target(&self, target: &str) -> SwipeAction {
+        match self {
+            Self::Command { .. } => {
+                let words: Vec<String> = match apps::split_exec(target) {
+                    Some(tokens) => tokens.into_iter().map(|(word, _)| word).collect(),
+                    None => target.split_whitespace().map(String::from).collect(),
+                };
+                let mut words = words.into_iter();
+                Self::Command {
+                    exec: words.next().unwrap_or_default(),
+                    args: words.collect(),
+                }
+            }
+            Self::OpenUrl(_) => Self::OpenUrl(target.trim().to_string()),
+            Self::Desktop(_) => Self::Desktop(target.trim().to_string()),
+            _ => self.clone(),
+        }
+    }
+}
+
+/// What resting the pointer on a slice does once the dwell time runs out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DwellAction {
+    /// Same as a left click
+    #[default]
+    Activate,
+    /// Same as a right click (context ring of desktop and window actions)
+    Actions,
+}
+
+/// Where running apps that aren't dock favorites go in the dock mirror
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum RunningPlacement {
+    /// After the favorites, in the order they were first seen running
+    #[default]
+    FirstSeen,
+    /// After the favorites, sorted by name
+    Alphabetical,
+    /// In their own section after a gap, in the order they were first seen
+    Separate,
+}
+
+/// Screen angle in radians for a pinned direction
+///
+/// Accepts compass names (`north`, `northeast`, ...), their abbreviations
+/// (`n`, `ne`, ...) or degrees clockwise from north (`"45"`). Angles are in
+/// screen coordinates like the pie's slices: 0 is east, increasing clockwise.
+pub fn pin_angle(direction: &str) -> Option<f32> {
+    let direction = direction.trim().to_lowercase();
+    let degrees = match direction.as_str() {
+        "north" | "n" => 0.0,
+        "northeast" | "ne" => 45.0,
+        "east" | "e" => 90.0,
+        "southeast" | "se" => 135.0,
+        "south" | "s" => 180.0,
+        "southwest" | "sw" => 225.0,
+        "west" | "w" => 270.0,
+        "northwest" | "nw" => 315.0,
+        other => other.parse::<f32>().ok().filter(|d| d.is_finite())?,
+    };
+    Some((degrees - 90.0_f32).to_radians())
+}
+
+/// A named group of apps shown as a nested pie (submenu) in the ring
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieGroup {
+    /// Name shown for the group slice
+    pub name: String,
+    /// Icon name or path (defaults to a folder icon)
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// App IDs folded into this group
+    pub apps: Vec<String>,
 }
 
 /// Configuration for pie menu gesture detection
@@ -86,24 +216,81 @@ pub struct PieMenuConfig {
     /// Trigger pie menu on middle mouse click
     #[serde(default)]
     pub middle_click_trigger: bool,
+    /// Marking-menu mode: hold the trigger, move toward a slice and release to pick it
+    #[serde(default)]
+    pub marking_menu: bool,
+    /// Touchpad selection: after the tap, drag two fingers toward a slice and lift to pick it
+    #[serde(default)]
+    pub touchpad_select: bool,
     /// Icon size in pixels
     #[serde(default = "default_icon_size")]
     pub icon_size: u16,
     /// Spacing between icons on the circumference
     #[serde(default = "default_icon_spacing")]
     pub icon_spacing: f32,
+    /// Most concentric rings the pie is split into (1 = a single ring)
+    #[serde(default = "default_max_rings")]
+    pub max_rings: usize,
+    /// Items on a ring before the rest overflow to the next ring out
+    #[serde(default = "default_ring_capacity")]
+    pub ring_capacity: usize,
+    /// Near a screen edge, fan the slices over the half (or quarter, in a
+    /// corner) facing into the screen instead of moving the pie off the cursor
+    #[serde(default = "default_true")]
+    pub fan_at_edges: bool,
     /// How far the selected icon pushes outward on hover
     #[serde(default = "default_hover_offset")]
     pub hover_offset: f32,
     /// Animation speed for hover effects (0.05 = slow, 0.5 = fast)
     #[serde(default = "default_animation_speed")]
     pub animation_speed: f32,
+    /// Hovering a slice this long (ms) triggers `dwell_action`; 0 turns dwell off
+    #[serde(default)]
+    pub dwell_ms: u64,
+    /// What a completed dwell does
+    #[serde(default)]
+    pub dwell_action: DwellAction,
+    /// Order the dock mirror by frecency (launch history) instead of dock order
+    #[serde(default)]
+    pub smart_order: bool,
+    /// How many often-launched apps from outside the dock to suggest (0 = none)
+    #[serde(default)]
+    pub suggested_count: usize,
+    /// Where running apps that aren't favorites are placed
+    #[serde(default)]
+    pub running_placement: RunningPlacement,
+    /// Items pinned to a direction, by item ID (see [`pin_angle`] for directions)
+    #[serde(default)]
+    pub pinned_directions: BTreeMap<String, String>,
+    /// Groups of apps folded into submenu slices
+    #[serde(default)]
+    pub groups: Vec<PieGroup>,
+    /// Menu opened by the multi-finger tap
+    #[serde(default = "default_menu")]
+    pub tap_menu: String,
+    /// Menu opened by swipes bound to the pie menu
+    #[serde(default = "default_menu")]
+    pub swipe_menu: String,
+    /// Menu opened by middle mouse click
+    #[serde(default = "default_menu")]
+    pub middle_click_menu: String,
+    /// Menu opened from the panel applet
+    #[serde(default = "default_menu")]
+    pub applet_menu: String,
+    /// Desktop IDs for window app_ids that can't be matched automatically
+    /// (e.g. `"crx_abcdef": "chrome-abcdef-Default"`)
+    #[serde(default)]
+    pub app_aliases: BTreeMap<String, String>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_menu() -> String {
+    "default".to_string()
+}
+
 fn default_swipe_threshold() -> i32 {
     300
 }
@@ -116,6 +303,14 @@ fn default_icon_spacing() -> f32 {
     75.0
 }
 
+fn default_max_rings() -> usize {
+    1
+}
+
+fn default_ring_capacity() -> usize {
+    12
+}
+
 fn default_hover_offset() -> f32 {
     25.0
 }
@@ -138,10 +333,27 @@ impl Default for PieMenuConfig {
             show_background: true,
             icon_only_highlight: false,
             middle_click_trigger: false,
+            marking_menu: false,
+            touchpad_select: false,
             icon_size: 48,
             icon_spacing: 75.0,
+            max_rings: 1,
+            ring_capacity: 12,
+            fan_at_edges: true,
             hover_offset: 25.0,
             animation_speed: 0.25,
+            dwell_ms: 0,
+            dwell_action: DwellAction::Activate,
+            smart_order: false,
+            suggested_count: 0,
+            running_placement: RunningPlacement::FirstSeen,
+            pinned_directions: BTreeMap::new(),
+            groups: Vec::new(),
+            tap_menu: default_menu(),
+            swipe_menu: default_menu(),
+            middle_click_menu: default_menu(),
+            applet_menu: default_menu(),
+            app_aliases: BTreeMap::new(),
         }
     }
 }
@@ -168,6 +380,16 @@ impl PieMenuConfig {
         }
     }
 
+    /// Menu opened by each trigger, as (config key, menu name)
+    pub fn menu_bindings(&self) -> [(&'static str, &str); 4] {
+        [
+            ("tap_menu", &self.tap_menu),
+            ("swipe_menu", &self.swipe_menu),
+            ("middle_click_menu", &self.middle_click_menu),
+            ("applet_menu", &self.applet_menu),
+        ]
+    }
+
     /// Save config to disk
     pub fn save(&self) -> Result<(), std::io::Error> {
         let path = Self::config_path();
@@ -201,6 +423,16 @@ pub struct GestureConfig {
     pub swipe_right: SwipeAction,
     /// Trigger pie menu on middle mouse click
     pub middle_click_trigger: bool,
+    /// Open on press and pick on release (marking-menu mode)
+    pub marking_menu: bool,
+    /// Pick slices with a touchpad drag after the tap
+    pub touchpad_select: bool,
+    /// Menu opened by the multi-finger tap
+    pub tap_menu: String,
+    /// Menu opened by swipes bound to the pie menu
+    pub swipe_menu: String,
+    /// Menu opened by middle mouse click
+    pub middle_click_menu: String,
 }
 
 impl Default for GestureConfig {
@@ -216,11 +448,16 @@ impl From<&PieMenuConfig> for GestureConfig {
             tap_max_duration: Duration::from_millis(config.tap_duration_ms),
             tap_max_movement: config.tap_movement,
             swipe_threshold: config.swipe_threshold,
-            swipe_up: config.swipe_up,
-            swipe_down: config.swipe_down,
-            swipe_left: config.swipe_left,
-            swipe_right: config.swipe_right,
+            swipe_up: config.swipe_up.clone(),
+            swipe_down: config.swipe_down.clone(),
+            swipe_left: config.swipe_left.clone(),
+            swipe_right: config.swipe_right.clone(),
             middle_click_trigger: config.middle_click_trigger,
+            marking_menu: config.marking_menu,
+            touchpad_select: config.touchpad_select,
+            tap_menu: config.tap_menu.clone(),
+            swipe_menu: config.swipe_menu.clone(),
+            middle_click_menu: config.middle_click_menu.clone(),
         }
     }
 }
@@ -331,6 +568,43 @@ pub fn read_dock_applets() -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_swipe_action_serde() {
+        // Built-in actions keep their plain string form
+        assert_eq!(serde_json::to_string(&SwipeAction::Workspaces).unwrap(), r#""Workspaces""#);
+
+        let action: SwipeAction =
+            serde_json::from_str(r#"{"Command": {"exec": "playerctl", "args": ["play-pause"]}}"#).unwrap();
+        assert_eq!(action.target().as_deref(), Some("playerctl play-pause"));
+        assert_eq!(action.with_target("  "), SwipeAction::Command { exec: String::new(), args: Vec::new() });
+
+        // Quoted arguments keep their spaces and survive the round trip
+        let action = action.with_target(r#"sh -c "notify-send 'a b' \"hi\"""#);
+        assert_eq!(
+            action,
+            SwipeAction::Command {
+                exec: "sh".to_string(),
+                args: vec!["-c".to_string(), r#"notify-send 'a b' "hi""#.to_string()],
+            }
+        );
+        assert_eq!(action.with_target(&action.target().unwrap()), action);
+
+        let action: SwipeAction = serde_json::from_str(r#"{"Command": {"exec": "cosmic-term"}}"#).unwrap();
+        assert_eq!(action.command(), None);
+        assert!(SwipeAction::all().iter().any(|a| a.same_kind(&action)));
+    }
+
+    #[test]
+    fn test_pin_angle() {
+        use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+        assert_eq!(pin_angle("north"), Some(-FRAC_PI_2));
+        assert_eq!(pin_angle(" NE "), Some(-FRAC_PI_4));
+        assert_eq!(pin_angle("east"), Some(0.0));
+        assert_eq!(pin_angle("180"), Some(FRAC_PI_2));
+        assert_eq!(pin_angle("up"), None);
+        assert_eq!(pin_angle("NaN"), None);
+    }
+
     #[test]
     fn test_read_favorites() {
         let favorites = read_favorites();
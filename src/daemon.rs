@@ -0,0 +1,193 @@
+//! Pie service socket
+//!
+//! With `--daemon`, one long-lived process opens pies on request, instead of
+//! each open starting a tracker process and then a pie process. Icon lookups
+//! and the desktop file index are kept between opens; the menu, running apps
+//! and theme are still read fresh for every pie so they're never stale.
+//! Requests arrive on a Unix socket in `$XDG_RUNTIME_DIR` (or a private
+//! directory in `/tmp` without it), one line per connection:
+//!
+//! ```text
+//! show <menu>             track the cursor, then open the menu there
+//! show-at <x> <y> <menu>  open the menu at a screen position
+//! hide                    close the open pie
+//! release [<angle>]       the held trigger was let go (marking-menu mode),
+//!                         having moved in this direction (radians)
+//! point <angle>           highlight the slice in this direction (touchpad selection)
+//! reload                  rescan desktop files and drop cached icons
+//! ```
+//!
+//! The applet, `--track` and `--pie-at` send these when the service is running
+//! and fall back to starting their own processes when it isn't. The service's
+//! D-Bus interface (see `dbus.rs`) feeds the same requests in.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+
+/// A request to the pie service
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    /// Track the cursor, then open the named menu there
+    Show { menu: String },
+    /// Open the named menu at a screen position
+    ShowAt { x: f32, y: f32, menu: String },
+    /// Close the open pie or cursor tracker
+    Hide,
+    /// The trigger held since `Show` was let go (marking-menu mode), after
+    /// moving in this direction (radians, screen coordinates) if it moved far
+    Release { angle: Option<f32> },
+    /// Highlight the slice in this direction (touchpad selection)
+    Point { angle: f32 },
+    /// Rescan desktop files and drop cached icons
+    Reload,
+}
+
+impl Request {
+    /// Format as a protocol line (without the newline)
+    pub fn to_line(&self) -> String {
+        match self {
+            Self::Show { menu } => format!("show {}", menu),
+            Self::ShowAt { x, y, menu } => format!("show-at {} {} {}", x, y, menu),
+            Self::Hide => "hide".to_string(),
+            Self::Release { angle: None } => "release".to_string(),
+            Self::Release { angle: Some(angle) } => format!("release {}", angle),
+            Self::Point { angle } => format!("point {}", angle),
+            Self::Reload => "reload".to_string(),
+        }
+    }
+
+    /// Parse a protocol line; menu names may contain spaces
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "show" if !rest.is_empty() => Some(Self::Show { menu: rest.to_string() }),
+            "show-at" => {
+                let mut parts = rest.splitn(3, ' ');
+                let x = parts.next()?.parse().ok()?;
+                let y = parts.next()?.parse().ok()?;
+                let menu = parts.next().filter(|m| !m.is_empty())?;
+                Some(Self::ShowAt { x, y, menu: menu.to_string() })
+            }
+            "hide" if rest.is_empty() => Some(Self::Hide),
+            "release" if rest.is_empty() => Some(Self::Release { angle: None }),
+            "release" => Some(Self::Release { angle: Some(rest.parse().ok()?) }),
+            "point" => Some(Self::Point { angle: rest.parse().ok()? }),
+            "reload" if rest.is_empty() => Some(Self::Reload),
+            _ => None,
+        }
+    }
+}
+
+/// Get the path to the service socket
+///
+/// Uses `$XDG_RUNTIME_DIR`, which only the user can access. Without it, the
+/// socket goes in a `/tmp/cosmic-pie-menu-<uid>` directory created with mode
+/// 0700; one that is owned by someone else or open to others is refused.
+fn socket_path() -> std::io::Result<PathBuf> {
+    if let Some(run_dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(run_dir).join("cosmic-pie-menu.sock"));
+    }
+
+    let uid = unsafe { libc::getuid() };
+    let dir = PathBuf::from(format!("/tmp/cosmic-pie-menu-{}", uid));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    let meta = std::fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() || meta.uid() != uid || meta.permissions().mode() & 0o077 != 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory owned by this user", dir.display()),
+        ));
+    }
+    Ok(dir.join("cosmic-pie-menu.sock"))
+}
+
+/// Send a request to the running pie service
+///
+/// Fails if no service is listening.
+pub fn send(request: &Request) -> std::io::Result<()> {
+    let mut stream = UnixStream::connect(socket_path()?)?;
+    writeln!(stream, "{}", request.to_line())
+}
+
+/// Listen for requests in a background thread
+///
+/// Only call this while holding the service lock, since it replaces any stale
+/// socket file. A file at the socket path that isn't a socket, or belongs to
+/// another user, is left alone and the service doesn't start. Requests are
+/// sent to `tx`, whose receiver the service's subscription reads (the same
+/// bridge the applet uses for gesture events).
+pub fn start_listener(tx: tokio::sync::mpsc::UnboundedSender<Request>) -> std::io::Result<()> {
+    let path = socket_path()?;
+    match std::fs::symlink_metadata(&path) {
+        Ok(meta) => {
+            if !meta.file_type().is_socket() || meta.uid() != unsafe { libc::getuid() } {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    format!("{} exists and is not this user's socket", path.display()),
+                ));
+            }
+            std::fs::remove_file(&path)?;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let listener = UnixListener::bind(&path)?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let mut line = String::new();
+            if BufReader::new(stream).read_line(&mut line).is_err() {
+                continue;
+            }
+            match Request::parse(&line) {
+                Some(request) => {
+                    if tx.send(request).is_err() {
+                        break;
+                    }
+                }
+                None => eprintln!("Ignoring unknown pie service request: {}", line.trim()),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_roundtrip() {
+        let requests = [
+            Request::Show { menu: "default".to_string() },
+            Request::ShowAt { x: 12.5, y: -3.0, menu: "my tools".to_string() },
+            Request::Hide,
+            Request::Reload,
+            Request::Release { angle: None },
+            Request::Release { angle: Some(-1.5) },
+            Request::Point { angle: 0.75 },
+        ];
+        for request in requests {
+            assert_eq!(Request::parse(&format!("{}\n", request.to_line())), Some(request));
+        }
+
+        assert_eq!(Request::parse("show"), None);
+        assert_eq!(Request::parse("show-at 1 x default"), None);
+        assert_eq!(Request::parse("show-at 1 2"), None);
+        assert_eq!(Request::parse("hide now"), None);
+        assert_eq!(Request::parse("release up"), None);
+        assert_eq!(Request::parse("point"), None);
+        assert_eq!(Request::parse("quit now"), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! D-Bus interface for the pie service
+//!
+//! The pie service (`--daemon`) owns `io.github.reality2_roycdavies.CosmicPieMenu`
+//! on the session bus, so shortcut daemons, scripts and other applets can drive
+//! the pie without spawning processes:
+//!
+//! ```text
+//! Show(s menu, d x, d y)    open the menu at a screen position
+//! ShowAtCursor(s menu)      track the cursor, then open the menu there
+//! Hide()                    close the open pie
+//! Reload()                  rescan desktop files and drop cached icons
+//! ListItems(s menu) -> a(sss)  the menu's top-level items (id, name, icon)
+//!
+//! signal ItemActivated(s id, s name)
+//! signal Closed()
+//! ```
+//!
+//! An empty menu name means the default menu. Calls are turned into the same
+//! requests as the service socket (see `daemon.rs`).
+//!
+//! Try it with:
+//!
+//! ```text
+//! busctl --user call io.github.reality2_roycdavies.CosmicPieMenu \
+//!     /io/github/reality2_roycdavies/CosmicPieMenu \
+//!     io.github.reality2_roycdavies.CosmicPieMenu ShowAtCursor s ""
+//! ```
+
+use std::sync::OnceLock;
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use zbus::object_server::SignalEmitter;
+
+use crate::apps::AppInfo;
+use crate::daemon::Request;
+use crate::menus;
+
+/// Well-known bus name of the pie service
+pub const BUS_NAME: &str = "io.github.reality2_roycdavies.CosmicPieMenu";
+
+/// Object path of the pie interface
+pub const OBJECT_PATH: &str = "/io/github/reality2_roycdavies/CosmicPieMenu";
+
+/// Name of the pie interface
+pub const INTERFACE: &str = "io.github.reality2_roycdavies.CosmicPieMenu";
+
+/// Something that happened in the pie, sent out as a D-Bus signal
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// An item was chosen
+    ItemActivated { id: String, name: String },
+    /// The pie (or the cursor tracker before it) closed
+    Closed,
+}
+
+/// Process-global sender for events, set when the interface starts
+static EVENTS: OnceLock<UnboundedSender<Event>> = OnceLock::new();
+
+/// Send an event out as a signal
+///
+/// Does nothing unless the D-Bus interface is running (pie subprocesses).
+pub fn emit(event: Event) {
+    if let Some(tx) = EVENTS.get() {
+        let _ = tx.send(event);
+    }
+}
+
+/// Use the default menu for an empty name
+fn menu_or_default(menu: &str) -> String {
+    if menu.is_empty() {
+        menus::DEFAULT_MENU.to_string()
+    } else {
+        menu.to_string()
+    }
+}
+
+/// The object served at [`OBJECT_PATH`]
+struct PieMenuInterface {
+    /// The pie service's request channel
+    requests: UnboundedSender<Request>,
+    /// Loads the items for a menu by name
+    load_apps: fn(&str) -> Vec<AppInfo>,
+}
+
+impl PieMenuInterface {
+    fn request(&self, request: Request) -> zbus::fdo::Result<()> {
+        self.requests
+            .send(request)
+            .map_err(|_| zbus::fdo::Error::Failed("The pie service has stopped".to_string()))
+    }
+}
+
+#[zbus::interface(name = "io.github.reality2_roycdavies.CosmicPieMenu")]
+impl PieMenuInterface {
+    /// Open the menu at a screen position
+    fn show(&self, menu: &str, x: f64, y: f64) -> zbus::fdo::Result<()> {
+        self.request(Request::ShowAt { x: x as f32, y: y as f32, menu: menu_or_default(menu) })
+    }
+
+    /// Track the cursor, then open the menu there
+    fn show_at_cursor(&self, menu: &str) -> zbus::fdo::Result<()> {
+        self.request(Request::Show { menu: menu_or_default(menu) })
+    }
+
+    /// Close the open pie
+    fn hide(&self) -> zbus::fdo::Result<()> {
+        self.request(Request::Hide)
+    }
+
+    /// Rescan desktop files and drop cached icons
+    fn reload(&self) -> zbus::fdo::Result<()> {
+        self.request(Request::Reload)
+    }
+
+    /// The menu's top-level items as (id, name, icon)
+    async fn list_items(&self, menu: &str) -> zbus::fdo::Result<Vec<(String, String, String)>> {
+        // Loading a menu queries running apps through a subprocess
+        let load_apps = self.load_apps;
+        let menu = menu_or_default(menu);
+        let apps = tokio::task::spawn_blocking(move || load_apps(&menu))
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        Ok(apps
+            .into_iter()
+            .filter(|app| !app.is_separator())
+            .map(|app| (app.id, app.name, app.icon.unwrap_or_default()))
+            .collect())
+    }
+
+    /// An item was chosen
+    #[zbus(signal)]
+    async fn item_activated(emitter: &SignalEmitter<'_>, id: &str, name: &str) -> zbus::Result<()>;
+
+    /// The pie closed
+    #[zbus(signal)]
+    async fn closed(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+/// Serve the interface and send out events until the event channel closes
+async fn serve(
+    address: Option<String>,
+    requests: UnboundedSender<Request>,
+    mut events: UnboundedReceiver<Event>,
+    load_apps: fn(&str) -> Vec<AppInfo>,
+) -> zbus::Result<()> {
+    let builder = match address {
+        Some(address) => zbus::connection::Builder::address(address.as_str())?,
+        None => zbus::connection::Builder::session()?,
+    };
+    let connection = builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, PieMenuInterface { requests, load_apps })?
+        .build()
+        .await?;
+    let interface = connection
+        .object_server()
+        .interface::<_, PieMenuInterface>(OBJECT_PATH)
+        .await?;
+
+    while let Some(event) = events.recv().await {
+        let emitter = interface.signal_emitter();
+        let result = match &event {
+            Event::ItemActivated { id, name } => PieMenuInterface::item_activated(emitter, id, name).await,
+            Event::Closed => PieMenuInterface::closed(emitter).await,
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to send {:?} signal: {}", event, e);
+        }
+    }
+    Ok(())
+}
+
+/// Run [`serve`] in a background thread with its own runtime
+fn spawn_server(
+    address: Option<String>,
+    requests: UnboundedSender<Request>,
+    events: UnboundedReceiver<Event>,
+    load_apps: fn(&str) -> Vec<AppInfo>,
+) {
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                eprintln!("Failed to start the D-Bus runtime: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = runtime.block_on(serve(address, requests, events, load_apps)) {
+            eprintln!("D-Bus interface not available: {}", e);
+        }
+    });
+}
+
+/// Serve the interface on the session bus in a background thread
+///
+/// Calls are sent to `requests`, the pie service's request channel.
+pub fn start(requests: UnboundedSender<Request>, load_apps: fn(&str) -> Vec<AppInfo>) {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    if EVENTS.set(tx).is_err() {
+        return;
+    }
+    spawn_server(None, requests, rx, load_apps);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    fn test_items(_menu: &str) -> Vec<AppInfo> {
+        vec![
+            AppInfo::command("Terminal", "cosmic-term", None),
+            AppInfo::separator(),
+        ]
+    }
+
+    /// A private bus daemon, stopped when the test ends (even on a failed assert)
+    struct PrivateBus(Child);
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    /// Runs against a private bus, so it needs `dbus-daemon` but no session
+    /// (`cargo test -- --ignored` to run it)
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn test_interface_on_private_bus() {
+        let mut bus = PrivateBus(
+            Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("failed to start dbus-daemon"),
+        );
+        let mut address = String::new();
+        BufReader::new(bus.0.stdout.take().unwrap()).read_line(&mut address).unwrap();
+        let address = address.trim().to_string();
+
+        let (requests_tx, mut requests_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (events_tx, events_rx) = tokio::sync::mpsc::unbounded_channel();
+        spawn_server(Some(address.clone()), requests_tx, events_rx, test_items);
+
+        let connection = zbus::blocking::connection::Builder::address(address.as_str())
+            .unwrap()
+            .build()
+            .unwrap();
+        let dbus = zbus::blocking::fdo::DBusProxy::new(&connection).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !dbus.name_has_owner(BUS_NAME.try_into().unwrap()).unwrap() {
+            assert!(Instant::now() < deadline, "service never claimed its name");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        let proxy = zbus::blocking::Proxy::new(&connection, BUS_NAME, OBJECT_PATH, INTERFACE).unwrap();
+
+        proxy.call_method("Show", &("tools", 10.0f64, 20.0f64)).unwrap();
+        assert_eq!(
+            requests_rx.blocking_recv(),
+            Some(Request::ShowAt { x: 10.0, y: 20.0, menu: "tools".to_string() })
+        );
+        proxy.call_method("ShowAtCursor", &("",)).unwrap();
+        assert_eq!(
+            requests_rx.blocking_recv(),
+            Some(Request::Show { menu: menus::DEFAULT_MENU.to_string() })
+        );
+        proxy.call_method("Hide", &()).unwrap();
+        assert_eq!(requests_rx.blocking_recv(), Some(Request::Hide));
+
+        let items: Vec<(String, String, String)> = proxy.call("ListItems", &("",)).unwrap();
+        assert_eq!(
+            items,
+            vec![(
+                "command:Terminal".to_string(),
+                "Terminal".to_string(),
+                "utilities-terminal".to_string()
+            )]
+        );
+
+        let mut activated = proxy.receive_signal("ItemActivated").unwrap();
+        events_tx
+            .send(Event::ItemActivated { id: "firefox".to_string(), name: "Firefox".to_string() })
+            .unwrap();
+        let signal = activated.next().unwrap();
+        let (id, name): (String, String) = signal.body().deserialize().unwrap();
+        assert_eq!((id.as_str(), name.as_str()), ("firefox", "Firefox"));
+    }
+}
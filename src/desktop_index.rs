@@ -0,0 +1,396 @@
+//! Desktop file index
+//!
+//! Finding an app's desktop file used to mean scanning every application
+//! directory, and since each pie is a fresh process that happened on every
+//! open. This index maps desktop IDs and `StartupWMClass` values to desktop
+//! files and is cached in `~/.cache/cosmic-pie-menu/desktop-index.json`.
+//!
+//! When the cache is loaded, each directory's modification time is compared
+//! with the one it was indexed at, and only directories that changed are
+//! rescanned. The applet also watches the directories and updates single
+//! entries as files are added, edited or removed.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use crate::apps::{desktop_file_dirs, desktop_lines};
+
+/// Bump when the cache format changes so old caches get rebuilt
+const INDEX_VERSION: u32 = 2;
+
+/// How long to wait for more file events before updating the index
+/// (package installs touch many files at once)
+const WATCH_BATCH_DELAY: Duration = Duration::from_millis(500);
+
+/// The index for this process, loaded on first lookup
+static INDEX: Mutex<Option<DesktopIndex>> = Mutex::new(None);
+
+/// One desktop file in the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Desktop ID (file name without `.desktop`)
+    id: String,
+    /// `StartupWMClass` of the entry, if set
+    wm_class: Option<String>,
+    /// Flatpak app ID (`X-Flatpak`), if installed as a Flatpak
+    flatpak: Option<String>,
+}
+
+/// The desktop files found in one application directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedDir {
+    path: PathBuf,
+    /// Modification time of the directory when it was scanned
+    /// (`None` if it didn't exist)
+    modified: Option<SystemTime>,
+    files: Vec<IndexedFile>,
+}
+
+/// Lookup table from app IDs to desktop files
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DesktopIndex {
+    version: u32,
+    /// Application directories, in lookup priority order
+    dirs: Vec<IndexedDir>,
+}
+
+impl DesktopIndex {
+    /// Get the path to the cache file
+    fn cache_path() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("cosmic-pie-menu")
+            .join("desktop-index.json")
+    }
+
+    /// Load the cached index, rescanning any directories that changed since
+    pub fn load() -> Self {
+        let mut index: Self = fs::read_to_string(Self::cache_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .filter(|index: &Self| index.version == INDEX_VERSION)
+            .unwrap_or_default();
+
+        if index.refresh(&desktop_file_dirs()) {
+            if let Err(e) = index.save() {
+                eprintln!("Failed to save desktop file index: {}", e);
+            }
+        }
+        index
+    }
+
+    /// Bring the index in line with `dirs`, rescanning changed directories
+    ///
+    /// Returns whether anything was rescanned.
+    fn refresh(&mut self, dirs: &[PathBuf]) -> bool {
+        let mut changed = self.version != INDEX_VERSION || self.dirs.len() != dirs.len();
+        let mut old_dirs = std::mem::take(&mut self.dirs);
+
+        for dir in dirs {
+            let modified = dir_modified(dir);
+            let cached = old_dirs
+                .iter()
+                .position(|d| &d.path == dir && d.modified == modified)
+                .map(|i| old_dirs.swap_remove(i));
+            match cached {
+                Some(cached) => self.dirs.push(cached),
+                None => {
+                    changed = true;
+                    self.dirs.push(scan_dir(dir));
+                }
+            }
+        }
+
+        self.version = INDEX_VERSION;
+        changed
+    }
+
+    /// Re-index a single desktop file after it was created, changed or removed
+    fn update_file(&mut self, path: &Path) {
+        let (Some(parent), Some(id)) = (path.parent(), desktop_id(path)) else {
+            return;
+        };
+        let Some(dir) = self.dirs.iter_mut().find(|d| d.path == parent) else {
+            return;
+        };
+
+        dir.files.retain(|f| f.id != id);
+        if let Some(file) = index_file(path) {
+            let pos = dir.files.partition_point(|f| f.id < file.id);
+            dir.files.insert(pos, file);
+        }
+        dir.modified = dir_modified(parent);
+    }
+
+    /// Write the index to the cache file
+    fn save(&self) -> Result<(), std::io::Error> {
+        let path = Self::cache_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        // Write then rename, so a pie opening meanwhile never reads half a file
+        let tmp = path.with_extension(format!("json.{}", std::process::id()));
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Find the desktop file for an app ID
+    ///
+    /// Tries an exact desktop ID first, then `StartupWMClass` and Flatpak IDs
+    /// (see [`Self::resolve`]), then IDs whose last dotted part matches (so
+    /// `Slack` finds `com.slack.Slack.desktop`). Earlier directories win at
+    /// each step.
+    pub fn lookup(&self, app_id: &str) -> Option<PathBuf> {
+        let app_id_lower = app_id.to_lowercase();
+        self.resolve_path(app_id).or_else(|| {
+            self.find(|f| {
+                let last_part = f.id.rsplit('.').next().unwrap_or(&f.id);
+                last_part.to_lowercase() == app_id_lower || f.id.to_lowercase() == app_id_lower
+            })
+        })
+    }
+
+    /// Get the desktop ID that a window's app_id belongs to
+    ///
+    /// Only uses exact keys: the desktop ID itself, `StartupWMClass` (PWAs,
+    /// Electron apps and Steam games, whose window app_id is their WM class)
+    /// and the Flatpak app ID, each compared ignoring case.
+    pub fn resolve(&self, app_id: &str) -> Option<String> {
+        self.resolve_path(app_id)
+            .and_then(|path| desktop_id(&path))
+    }
+
+    fn resolve_path(&self, app_id: &str) -> Option<PathBuf> {
+        let matches = |key: &Option<String>| key.as_ref().is_some_and(|k| k.eq_ignore_ascii_case(app_id));
+        self.find(|f| f.id == app_id)
+            .or_else(|| self.find(|f| matches(&f.wm_class)))
+            .or_else(|| self.find(|f| matches(&f.flatpak)))
+            .or_else(|| self.find(|f| f.id.eq_ignore_ascii_case(app_id)))
+    }
+
+    /// Path of the first indexed file matching a predicate, in priority order
+    fn find(&self, matches: impl Fn(&IndexedFile) -> bool) -> Option<PathBuf> {
+        self.dirs.iter().find_map(|dir| {
+            dir.files
+                .iter()
+                .find(|f| matches(f))
+                .map(|f| dir.path.join(format!("{}.desktop", f.id)))
+        })
+    }
+}
+
+/// Get a directory's modification time, or `None` if it doesn't exist
+fn dir_modified(dir: &Path) -> Option<SystemTime> {
+    fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+/// Get the desktop ID of a path if it names a desktop file
+fn desktop_id(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_suffix(".desktop").map(String::from)
+}
+
+/// Index every desktop file in a directory
+fn scan_dir(dir: &Path) -> IndexedDir {
+    let mut files: Vec<IndexedFile> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| index_file(&e.path()))
+                .collect()
+        })
+        .unwrap_or_default();
+    // read_dir order is arbitrary; keep lookups deterministic
+    files.sort_by(|a, b| a.id.cmp(&b.id));
+
+    IndexedDir {
+        path: dir.to_path_buf(),
+        modified: dir_modified(dir),
+        files,
+    }
+}
+
+/// Read the indexed fields of one desktop file
+fn index_file(path: &Path) -> Option<IndexedFile> {
+    let id = desktop_id(path)?;
+    let content = fs::read_to_string(path).ok()?;
+
+    let mut wm_class = None;
+    let mut flatpak = None;
+    let mut exec = None;
+    for line in desktop_lines(&content) {
+        if line.group != "Desktop Entry" || line.locale.is_some() || line.value.is_empty() {
+            continue;
+        }
+        match line.key {
+            "StartupWMClass" => wm_class = Some(line.value),
+            "X-Flatpak" => flatpak = Some(line.value),
+            "Exec" => exec = Some(line.value),
+            _ => {}
+        }
+    }
+
+    // Steam game shortcuts don't set StartupWMClass, but the games' windows
+    // use steam_app_<id> for the ID in the steam://rungameid/<id> launch URL
+    if wm_class.is_none() {
+        wm_class = exec
+            .as_deref()
+            .and_then(|exec| exec.split("steam://rungameid/").nth(1))
+            .map(|rest| rest.chars().take_while(char::is_ascii_digit).collect::<String>())
+            .filter(|game| !game.is_empty())
+            .map(|game| format!("steam_app_{}", game));
+    }
+
+    Some(IndexedFile { id, wm_class, flatpak })
+}
+
+/// Find the desktop file for an app ID using this process's index
+pub fn find_desktop_file(app_id: &str) -> Option<PathBuf> {
+    let mut index = INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    index.get_or_insert_with(DesktopIndex::load).lookup(app_id)
+}
+
+/// Get the desktop ID a window's app_id belongs to using this process's index
+pub fn resolve_app_id(app_id: &str) -> Option<String> {
+    let mut index = INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    index.get_or_insert_with(DesktopIndex::load).resolve(app_id)
+}
+
+/// Every indexed desktop file, one per desktop ID (earlier directories win)
+pub fn all_desktop_files() -> Vec<PathBuf> {
+    let mut index = INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    let index = index.get_or_insert_with(DesktopIndex::load);
+    let mut seen = BTreeSet::new();
+    index
+        .dirs
+        .iter()
+        .flat_map(|dir| dir.files.iter().map(move |f| (dir, f)))
+        .filter(|(_, f)| seen.insert(f.id.clone()))
+        .map(|(dir, f)| dir.path.join(format!("{}.desktop", f.id)))
+        .collect()
+}
+
+/// Rescan every application directory, replacing this process's index
+pub fn rebuild() {
+    let mut index = DesktopIndex::default();
+    index.refresh(&desktop_file_dirs());
+    if let Err(e) = index.save() {
+        eprintln!("Failed to save desktop file index: {}", e);
+    }
+    *INDEX.lock().unwrap_or_else(|e| e.into_inner()) = Some(index);
+}
+
+/// Watch the application directories and keep the cached index up to date
+///
+/// Runs in a background thread for the life of the process.
+pub fn start_watcher() -> notify::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for dir in desktop_file_dirs() {
+        // Directories that don't exist yet are picked up by the load-time check
+        if dir.is_dir() {
+            if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                eprintln!("Failed to watch {}: {}", dir.display(), e);
+            }
+        }
+    }
+
+    std::thread::spawn(move || {
+        // The watcher stops when dropped, so keep it alive in this thread
+        let _watcher = watcher;
+        let event_paths = |event: notify::Result<notify::Event>| {
+            event.map(|e| e.paths).unwrap_or_default()
+        };
+
+        while let Ok(event) = rx.recv() {
+            let mut changed: BTreeSet<PathBuf> = event_paths(event).into_iter().collect();
+            while let Ok(event) = rx.recv_timeout(WATCH_BATCH_DELAY) {
+                changed.extend(event_paths(event));
+            }
+            changed.retain(|path| desktop_id(path).is_some());
+            if changed.is_empty() {
+                continue;
+            }
+
+            let mut index = INDEX.lock().unwrap_or_else(|e| e.into_inner());
+            let index = index.get_or_insert_with(DesktopIndex::load);
+            for path in &changed {
+                index.update_file(path);
+            }
+            match index.save() {
+                Ok(()) => println!("Desktop file index updated ({} changed)", changed.len()),
+                Err(e) => eprintln!("Failed to save desktop file index: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_lookup_and_update() {
+        let dir = std::env::temp_dir().join(format!("cosmic-pie-menu-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("com.slack.Slack.desktop"), "[Desktop Entry]\nName=Slack\n").unwrap();
+        fs::write(
+            dir.join("code.desktop"),
+            "[Desktop Entry]\nName=VS Code\nStartupWMClass=Code\n\n[Desktop Action new]\nStartupWMClass=Other\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("chrome-abc-Default.desktop"),
+            "[Desktop Entry]\nName=Docs\nStartupWMClass=crx_abc\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("steam_game.desktop"),
+            "[Desktop Entry]\nName=Game\nExec=steam steam://rungameid/620\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("org.example.App.desktop"),
+            "[Desktop Entry]\nName=Example\nX-Flatpak=org.example.Tool\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a desktop file").unwrap();
+
+        let mut index = DesktopIndex::default();
+        assert!(index.refresh(std::slice::from_ref(&dir)));
+        assert!(!index.refresh(std::slice::from_ref(&dir)));
+
+        assert_eq!(index.lookup("code"), Some(dir.join("code.desktop")));
+        assert_eq!(index.lookup("Code"), Some(dir.join("code.desktop")));
+        assert_eq!(index.lookup("slack"), Some(dir.join("com.slack.Slack.desktop")));
+        assert_eq!(index.lookup("Other"), None);
+        assert_eq!(index.lookup("notes"), None);
+
+        assert_eq!(index.resolve("crx_abc").as_deref(), Some("chrome-abc-Default"));
+        assert_eq!(index.resolve("steam_app_620").as_deref(), Some("steam_game"));
+        assert_eq!(index.resolve("org.example.Tool").as_deref(), Some("org.example.App"));
+        // Only exact keys resolve; the dotted-suffix guess is for lookups
+        assert_eq!(index.resolve("slack"), None);
+
+        fs::write(dir.join("firefox.desktop"), "[Desktop Entry]\nName=Firefox\n").unwrap();
+        index.update_file(&dir.join("firefox.desktop"));
+        assert_eq!(index.lookup("firefox"), Some(dir.join("firefox.desktop")));
+
+        fs::remove_file(dir.join("code.desktop")).unwrap();
+        index.update_file(&dir.join("code.desktop"));
+        assert_eq!(index.lookup("Code"), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
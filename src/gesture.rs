@@ -18,6 +18,7 @@
 use evdev::{AbsoluteAxisType, Device, InputEventKind, Key, RelativeAxisType};
 use std::os::unix::io::AsRawFd;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
 use std::time::{Duration, Instant};
 
@@ -210,6 +211,17 @@ enum GestureState {
         /// When the pending trigger was set
         pending_since: Instant,
     },
+    /// The tap opened the pie; two fingers dragged across the touchpad now
+    /// point at a slice and lifting picks it (touchpad selection). One finger
+    /// just moves the pointer, and the tap's fingers start a new tap or swipe.
+    Selecting {
+        /// When the pie was opened
+        since: Instant,
+        /// Tracker for the fingers on the touchpad, if any
+        tracker: Option<MultiTouchTracker>,
+        /// Direction last reported to the pie
+        pointed: Option<f32>,
+    },
 }
 
 /// Calculate swipe direction from movement deltas
@@ -371,6 +383,34 @@ fn is_mouse_with_middle_button(device: &Device) -> bool {
 /// Debounce time for 3-finger mode to avoid false triggers on 3→4 transitions
 const PENDING_TRIGGER_DEBOUNCE: Duration = Duration::from_millis(150);
 
+/// How far the mouse must move (in mouse counts) with the middle button held
+/// for the release to pick the slice in that direction (marking-menu mode)
+const MARK_MOUSE_THRESHOLD: i32 = 40;
+
+/// How long after the tap a touchpad drag can still pick a slice
+const TOUCHPAD_SELECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How far (in radians) the drag direction must turn before the pie is told again
+const POINT_RESOLUTION: f32 = 0.05;
+
+/// Direction of a movement in radians, in screen coordinates
+/// (0 = right, increasing clockwise), matching the pie's slice angles
+fn movement_angle(dx: i32, dy: i32) -> f32 {
+    (dy as f32).atan2(dx as f32)
+}
+
+/// Direction the tracked fingers moved in, once they moved further than a tap allows
+fn drag_angle(tracker: &MultiTouchTracker, threshold: i32) -> Option<f32> {
+    let (avg_dx, avg_dy) = tracker.average_movement();
+    (avg_dx.abs().max(avg_dy.abs()) > threshold).then(|| movement_angle(avg_dx, avg_dy))
+}
+
+/// Smallest difference between two angles in radians
+fn angle_between(a: f32, b: f32) -> f32 {
+    let diff = (a - b).rem_euclid(std::f32::consts::TAU);
+    diff.min(std::f32::consts::TAU - diff)
+}
+
 /// Direction of a swipe gesture (relative to touchpad orientation)
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SwipeDirection {
@@ -397,6 +437,13 @@ enum GestureEvent {
     TriggerCancelled,
     /// Swipe detected - triggered immediately when movement exceeds threshold
     SwipeDetected(SwipeDirection),
+    /// Fingers lifted in marking-menu mode, with the direction they moved in
+    /// if they moved further than a tap allows
+    Marked(Option<f32>),
+    /// Fingers dragging after the tap point in a new direction (touchpad selection)
+    Pointing(f32),
+    /// Fingers lifted after dragging in this direction (touchpad selection)
+    Selected(f32),
 }
 
 /// Process a single evdev input event and update gesture state.
@@ -407,6 +454,8 @@ enum GestureEvent {
 /// - `FingersUp`: Quick tap detected (short duration, little movement)
 /// - `SwipeDetected`: Movement exceeded threshold, swipe direction determined
 /// - `TriggerCancelled`: Gesture was cancelled (e.g., extra finger added)
+/// - `Marked`: Fingers lifted in marking-menu mode (no taps or swipes then)
+/// - `Pointing` / `Selected`: A drag after the tap points at / picks a slice
 /// - `None`: No significant state change
 fn process_event(
     event: &evdev::InputEvent,
@@ -415,6 +464,7 @@ fn process_event(
     tap_max_duration: Duration,
     tap_max_movement: i32,
     swipe_threshold: i32,
+    marking_menu: bool,
 ) -> GestureEvent {
     // Determine which key to watch based on finger count
     let tap_key = if finger_count == 3 {
@@ -431,6 +481,24 @@ fn process_event(
     };
 
     match event.kind() {
+        // Touchpad selection: two fingers touching down after the tap start a drag
+        // (the pie covers the screen, so their scrolling reaches nothing else)
+        InputEventKind::Key(Key::BTN_TOOL_DOUBLETAP) if matches!(state, GestureState::Selecting { .. }) => {
+            if let GestureState::Selecting { tracker, pointed, .. } = state {
+                if event.value() == 1 {
+                    *tracker = Some(MultiTouchTracker::new(2));
+                    *pointed = None;
+                } else if let Some(tracker) = tracker.take() {
+                    // Fingers leave their slots before the key goes up, so fall
+                    // back to the last direction pointed at. A touch that didn't
+                    // drag leaves the pie open and keeps watching.
+                    if let Some(angle) = drag_angle(&tracker, tap_max_movement).or(*pointed) {
+                        *state = GestureState::Idle;
+                        return GestureEvent::Selected(angle);
+                    }
+                }
+            }
+        }
         InputEventKind::Key(key) if key == tap_key => {
             if event.value() == 1 {
                 // Fingers went down - record the time and start fresh tracker
@@ -441,6 +509,14 @@ fn process_event(
                     tracker: MultiTouchTracker::new(min_fingers),
                 };
                 return GestureEvent::FingersDown;
+            } else if event.value() == 0 && marking_menu {
+                // Fingers lifted - the pie opened on touch down, so this picks a slice
+                if let GestureState::FingersDown { tracker, .. } = &*state {
+                    let (avg_dx, avg_dy) = tracker.average_movement();
+                    let moved = avg_dx.abs().max(avg_dy.abs()) > tap_max_movement;
+                    *state = GestureState::Idle;
+                    return GestureEvent::Marked(moved.then(|| movement_angle(avg_dx, avg_dy)));
+                }
             } else if event.value() == 0 {
                 // Fingers lifted - check if it was a quick tap (not a swipe)
                 if let GestureState::FingersDown { start, ref tracker } = state.clone() {
@@ -482,6 +558,12 @@ fn process_event(
         }
         // In 3-finger mode, watch for 4-finger to cancel pending trigger
         InputEventKind::Key(key) if Some(key) == cancel_key && event.value() == 1 => {
+            if marking_menu {
+                // The pie may already be open for the 3-finger hold (or just released)
+                *state = GestureState::Idle;
+                println!("3-finger hold cancelled (4th finger detected)");
+                return GestureEvent::TriggerCancelled;
+            }
             if let GestureState::PendingTrigger { .. } = *state {
                 // 4th finger went down while we had a pending 3-finger trigger
                 // This is a 3→4 transition, cancel the trigger
@@ -490,104 +572,117 @@ fn process_event(
                 return GestureEvent::TriggerCancelled;
             }
         }
-        // Track multitouch position while fingers are down
+        // Track multitouch position while fingers are down (or dragging to select)
         InputEventKind::AbsAxis(axis) => {
-            if let GestureState::FingersDown { ref mut tracker, .. } = state {
-                let val = event.value();
-                match axis {
-                    // ABS_MT_SLOT tells us which finger slot the following events apply to
-                    AbsoluteAxisType::ABS_MT_SLOT => {
-                        let slot = val as usize;
-                        if slot < MAX_SLOTS {
-                            tracker.current_slot = slot;
-                        }
-                    }
-                    // ABS_MT_TRACKING_ID: >= 0 means finger down, -1 means finger up
-                    AbsoluteAxisType::ABS_MT_TRACKING_ID => {
-                        let slot = tracker.current_slot;
-                        if slot < MAX_SLOTS {
-                            tracker.slots[slot].active = val >= 0;
-                        }
-                    }
-                    // Track X position for current slot
-                    AbsoluteAxisType::ABS_MT_POSITION_X => {
-                        let slot = tracker.current_slot;
-                        if slot < MAX_SLOTS {
-                            // Capture start position on first X event for this slot
-                            if tracker.slots[slot].start_x.is_none() {
-                                tracker.slots[slot].start_x = Some(val);
-                            }
-                            tracker.slots[slot].x = val;
-                            tracker.slots[slot].active = true;
-                            tracker.mark_event();
-                            tracker.try_capture_start();
-
-                            // Check for early swipe detection
-                            if tracker.start_captured {
-                                if let Some(dir) = check_early_swipe(tracker, swipe_threshold) {
-                                    *state = GestureState::Idle;
-                                    return GestureEvent::SwipeDetected(dir);
-                                }
-                            }
-                        }
+            let (tracker, early_swipe) = match state {
+                GestureState::FingersDown { tracker, .. } => (tracker, !marking_menu),
+                GestureState::Selecting { tracker: Some(tracker), .. } => (tracker, false),
+                _ => return GestureEvent::None,
+            };
+            let val = event.value();
+            match axis {
+                // ABS_MT_SLOT tells us which finger slot the following events apply to
+                AbsoluteAxisType::ABS_MT_SLOT => {
+                    let slot = val as usize;
+                    if slot < MAX_SLOTS {
+                        tracker.current_slot = slot;
                     }
-                    // Track Y position for current slot
-                    AbsoluteAxisType::ABS_MT_POSITION_Y => {
-                        let slot = tracker.current_slot;
-                        if slot < MAX_SLOTS {
-                            // Capture start position on first Y event for this slot
-                            if tracker.slots[slot].start_y.is_none() {
-                                tracker.slots[slot].start_y = Some(val);
-                            }
-                            tracker.slots[slot].y = val;
-                            tracker.slots[slot].active = true;
-                            tracker.mark_event();
-                            tracker.try_capture_start();
-
-                            // Check for early swipe detection
-                            if tracker.start_captured {
-                                if let Some(dir) = check_early_swipe(tracker, swipe_threshold) {
-                                    *state = GestureState::Idle;
-                                    return GestureEvent::SwipeDetected(dir);
-                                }
-                            }
-                        }
+                }
+                // ABS_MT_TRACKING_ID: >= 0 means finger down, -1 means finger up
+                AbsoluteAxisType::ABS_MT_TRACKING_ID => {
+                    let slot = tracker.current_slot;
+                    if slot < MAX_SLOTS {
+                        tracker.slots[slot].active = val >= 0;
                     }
-                    // Fallback for non-MT touchpads (single-touch style reporting)
-                    AbsoluteAxisType::ABS_X => {
-                        // Use slot 0 for legacy single-touch
-                        if tracker.slots[0].start_x.is_none() {
-                            tracker.slots[0].start_x = Some(val);
+                }
+                // Track X position for current slot
+                AbsoluteAxisType::ABS_MT_POSITION_X => {
+                    let slot = tracker.current_slot;
+                    if slot < MAX_SLOTS {
+                        // Capture start position on first X event for this slot
+                        if tracker.slots[slot].start_x.is_none() {
+                            tracker.slots[slot].start_x = Some(val);
                         }
-                        tracker.slots[0].x = val;
-                        tracker.slots[0].active = true;
+                        tracker.slots[slot].x = val;
+                        tracker.slots[slot].active = true;
                         tracker.mark_event();
                         tracker.try_capture_start();
 
-                        if tracker.start_captured {
+                        // Check for early swipe detection
+                        if tracker.start_captured && early_swipe {
                             if let Some(dir) = check_early_swipe(tracker, swipe_threshold) {
                                 *state = GestureState::Idle;
                                 return GestureEvent::SwipeDetected(dir);
                             }
                         }
                     }
-                    AbsoluteAxisType::ABS_Y => {
-                        if tracker.slots[0].start_y.is_none() {
-                            tracker.slots[0].start_y = Some(val);
+                }
+                // Track Y position for current slot
+                AbsoluteAxisType::ABS_MT_POSITION_Y => {
+                    let slot = tracker.current_slot;
+                    if slot < MAX_SLOTS {
+                        // Capture start position on first Y event for this slot
+                        if tracker.slots[slot].start_y.is_none() {
+                            tracker.slots[slot].start_y = Some(val);
                         }
-                        tracker.slots[0].y = val;
-                        tracker.slots[0].active = true;
+                        tracker.slots[slot].y = val;
+                        tracker.slots[slot].active = true;
                         tracker.mark_event();
                         tracker.try_capture_start();
 
-                        if tracker.start_captured {
+                        // Check for early swipe detection
+                        if tracker.start_captured && early_swipe {
                             if let Some(dir) = check_early_swipe(tracker, swipe_threshold) {
                                 *state = GestureState::Idle;
                                 return GestureEvent::SwipeDetected(dir);
                             }
                         }
                     }
-                    _ => {}
+                }
+                // Fallback for non-MT touchpads (single-touch style reporting)
+                AbsoluteAxisType::ABS_X => {
+                    // Use slot 0 for legacy single-touch
+                    if tracker.slots[0].start_x.is_none() {
+                        tracker.slots[0].start_x = Some(val);
+                    }
+                    tracker.slots[0].x = val;
+                    tracker.slots[0].active = true;
+                    tracker.mark_event();
+                    tracker.try_capture_start();
+
+                    if tracker.start_captured && early_swipe {
+                        if let Some(dir) = check_early_swipe(tracker, swipe_threshold) {
+                            *state = GestureState::Idle;
+                            return GestureEvent::SwipeDetected(dir);
+                        }
+                    }
+                }
+                AbsoluteAxisType::ABS_Y => {
+                    if tracker.slots[0].start_y.is_none() {
+                        tracker.slots[0].start_y = Some(val);
+                    }
+                    tracker.slots[0].y = val;
+                    tracker.slots[0].active = true;
+                    tracker.mark_event();
+                    tracker.try_capture_start();
+
+                    if tracker.start_captured && early_swipe {
+                        if let Some(dir) = check_early_swipe(tracker, swipe_threshold) {
+                            *state = GestureState::Idle;
+                            return GestureEvent::SwipeDetected(dir);
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            // Tell the pie whenever the drag turns toward another direction
+            if let GestureState::Selecting { tracker: Some(tracker), pointed, .. } = state {
+                if let Some(angle) = drag_angle(tracker, tap_max_movement) {
+                    if pointed.is_none_or(|last| angle_between(last, angle) > POINT_RESOLUTION) {
+                        *pointed = Some(angle);
+                        return GestureEvent::Pointing(angle);
+                    }
                 }
             }
         }
@@ -626,6 +721,58 @@ fn check_pending_trigger(state: &mut GestureState) -> bool {
     false
 }
 
+/// End touchpad selection once the pie has been left alone for too long
+fn check_selecting_timeout(state: &mut GestureState) {
+    if let GestureState::Selecting { since, tracker: None, .. } = state {
+        if since.elapsed() >= TOUCHPAD_SELECT_TIMEOUT {
+            *state = GestureState::Idle;
+        }
+    }
+}
+
+/// Launch a custom swipe action (`Command`, `OpenUrl` or `Desktop`)
+fn spawn_custom_action(action: &SwipeAction) -> std::io::Result<std::process::Child> {
+    let mut command = match action {
+        SwipeAction::Command { exec, args } if !exec.is_empty() => {
+            let mut command = Command::new(exec);
+            command.args(args);
+            command
+        }
+        SwipeAction::OpenUrl(url) if !url.is_empty() => {
+            let mut command = Command::new("xdg-open");
+            command.arg(url);
+            command
+        }
+        SwipeAction::Desktop(app_id) if !app_id.is_empty() => {
+            let app = crate::apps::load_app_info(app_id).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no desktop file found for app id '{}'", app_id),
+                )
+            })?;
+            let args = app.launch_args().ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("desktop file for '{}' has no usable Exec", app_id),
+                )
+            })?;
+            let mut command = Command::new(&args[0]);
+            command.args(&args[1..]);
+            if let Some(dir) = app.working_dir.filter(|dir| dir.is_dir()) {
+                command.current_dir(dir);
+            }
+            command
+        }
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "no program, URL or app configured",
+            ))
+        }
+    };
+    command.spawn()
+}
+
 /// Set a device's file descriptor to non-blocking mode
 fn set_nonblocking(device: &Device) {
     let fd = device.as_raw_fd();
@@ -662,6 +809,15 @@ fn try_gesture_lock() -> Option<std::fs::File> {
     }
 }
 
+/// Set while the pie service isn't running, which turns off the modes that
+/// send it the release and drag
+static PIE_SERVICE_MISSING: AtomicBool = AtomicBool::new(false);
+
+/// Record whether the pie service is missing, returning the previous value
+pub fn set_pie_service_missing(missing: bool) -> bool {
+    PIE_SERVICE_MISSING.swap(missing, Ordering::Relaxed)
+}
+
 fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
     // Acquire a system-wide lock so only one applet instance runs gesture detection.
     // On multi-monitor setups COSMIC may launch multiple applet processes; without
@@ -751,6 +907,9 @@ fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
     // Stores (action, direction) so we know what to close and which direction opened it
     let mut last_opened: Option<(SwipeAction, SwipeDirection)> = None;
 
+    // Mouse movement since the middle button went down (marking-menu mode)
+    let mut middle_held: Option<(i32, i32)> = None;
+
     loop {
         // Periodically reload config from disk (for settings changes from subprocess)
         if last_config_check.elapsed() > config_check_interval {
@@ -798,6 +957,13 @@ fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
             last_config_check = Instant::now();
         }
 
+        // Marking-menu mode and touchpad selection only work with the pie
+        // service, since a fallback pie process can't hear the release
+        if PIE_SERVICE_MISSING.load(Ordering::Relaxed) {
+            current_cfg.marking_menu = false;
+            current_cfg.touchpad_select = false;
+        }
+
         let cfg = &current_cfg;
 
         // Check if finger count changed - need to rescan devices
@@ -860,19 +1026,43 @@ fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
                             cfg.tap_max_duration,
                             cfg.tap_max_movement,
                             cfg.swipe_threshold,
+                            cfg.marking_menu,
                         ) {
                             GestureEvent::FingersDown => {
                                 println!("{} fingers down - icon highlighted", cfg.finger_count);
                                 let _ = tx.send(GestureMessage::FingersDown);
+                                if cfg.marking_menu {
+                                    // Marking-menu mode opens on touch down and picks on lift
+                                    if tx.send(GestureMessage::ShowPieMenu(cfg.tap_menu.clone())).is_err() {
+                                        return;
+                                    }
+                                }
+                            }
+                            GestureEvent::Marked(angle) => {
+                                println!("{} fingers lifted - direction {:?}", cfg.finger_count, angle);
+                                let _ = tx.send(GestureMessage::Release(angle));
                             }
                             GestureEvent::FingersUp => {
                                 println!("{} fingers up - launching menu", cfg.finger_count);
-                                if tx.send(GestureMessage::ShowPieMenu).is_err() {
+                                if tx.send(GestureMessage::ShowPieMenu(cfg.tap_menu.clone())).is_err() {
                                     return;
                                 }
+                                if cfg.touchpad_select {
+                                    state = GestureState::Selecting { since: Instant::now(), tracker: None, pointed: None };
+                                }
+                            }
+                            GestureEvent::Pointing(angle) => {
+                                let _ = tx.send(GestureMessage::Point(angle));
+                            }
+                            GestureEvent::Selected(angle) => {
+                                println!("Touchpad drag picked direction {}", angle);
+                                let _ = tx.send(GestureMessage::Release(Some(angle)));
                             }
                             GestureEvent::TriggerCancelled => {
                                 let _ = tx.send(GestureMessage::Reset);
+                                if cfg.marking_menu {
+                                    let _ = tx.send(GestureMessage::HidePieMenu);
+                                }
                             }
                             GestureEvent::SwipeDetected(direction) => {
                                 let _ = tx.send(GestureMessage::Reset);
@@ -895,20 +1085,20 @@ fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
                                 }
 
                                 // Check if something is already open - any swipe closes it
-                                let (action_to_run, is_closing) = if let Some((prev_action, prev_dir)) = last_opened {
+                                let (action_to_run, is_closing) = if let Some((prev_action, prev_dir)) = &last_opened {
                                     // Something is open - close it with any swipe direction
                                     println!(
                                         "Swipe {:?} while {:?} open (opened with {:?}) - closing",
                                         direction, prev_action, prev_dir
                                     );
-                                    (prev_action, true)
+                                    (prev_action.clone(), true)
                                 } else {
                                     // Nothing open - get configured action for this direction
                                     let action = match direction {
-                                        SwipeDirection::Up => cfg.swipe_up,
-                                        SwipeDirection::Down => cfg.swipe_down,
-                                        SwipeDirection::Left => cfg.swipe_left,
-                                        SwipeDirection::Right => cfg.swipe_right,
+                                        SwipeDirection::Up => cfg.swipe_up.clone(),
+                                        SwipeDirection::Down => cfg.swipe_down.clone(),
+                                        SwipeDirection::Left => cfg.swipe_left.clone(),
+                                        SwipeDirection::Right => cfg.swipe_right.clone(),
                                     };
                                     (action, false)
                                 };
@@ -924,10 +1114,23 @@ fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
                                         // Pie menu doesn't need toggle tracking
                                         println!("Swipe {:?} - launching pie menu", direction);
                                         last_opened = None;
-                                        if tx.send(GestureMessage::ShowPieMenu).is_err() {
+                                        if tx.send(GestureMessage::ShowPieMenu(cfg.swipe_menu.clone())).is_err() {
                                             return;
                                         }
                                     }
+                                    SwipeAction::Command { .. } | SwipeAction::OpenUrl(_) | SwipeAction::Desktop(_) => {
+                                        // Custom actions aren't overlays, so there's nothing to toggle
+                                        match spawn_custom_action(&action_to_run) {
+                                            Ok(child) => println!(
+                                                "Swipe {:?} - launched {:?} (pid {})",
+                                                direction, action_to_run, child.id()
+                                            ),
+                                            Err(e) => eprintln!(
+                                                "Swipe {:?} - failed to launch {:?}: {}",
+                                                direction, action_to_run, e
+                                            ),
+                                        }
+                                    }
                                     _ => {
                                         // Execute the command (toggles the overlay)
                                         if let Some(cmd) = action_to_run.command() {
@@ -994,14 +1197,34 @@ fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
             match mouse.device.fetch_events() {
                 Ok(events) => {
                     for event in events {
-                        if let InputEventKind::Key(Key::BTN_MIDDLE) = event.kind() {
-                            if event.value() == 1 {
+                        match event.kind() {
+                            InputEventKind::Key(Key::BTN_MIDDLE) if event.value() == 1 => {
                                 // Button pressed
                                 println!("Middle mouse click - launching menu");
-                                if tx.send(GestureMessage::ShowPieMenu).is_err() {
+                                if tx.send(GestureMessage::ShowPieMenu(cfg.middle_click_menu.clone())).is_err() {
                                     return;
                                 }
+                                if cfg.marking_menu {
+                                    middle_held = Some((0, 0));
+                                }
                             }
+                            InputEventKind::Key(Key::BTN_MIDDLE) if event.value() == 0 => {
+                                // Button released - in marking-menu mode this picks a slice
+                                if let Some((dx, dy)) = middle_held.take() {
+                                    let moved = dx.abs().max(dy.abs()) > MARK_MOUSE_THRESHOLD;
+                                    let _ = tx.send(GestureMessage::Release(moved.then(|| movement_angle(dx, dy))));
+                                }
+                            }
+                            InputEventKind::RelAxis(axis) => {
+                                if let Some((dx, dy)) = middle_held.as_mut() {
+                                    match axis {
+                                        RelativeAxisType::REL_X => *dx += event.value(),
+                                        RelativeAxisType::REL_Y => *dy += event.value(),
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            _ => {}
                         }
                     }
                 }
@@ -1041,10 +1264,14 @@ fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
         // Check for pending trigger timeout (3-finger mode debounce)
         if check_pending_trigger(&mut state) {
             println!("{} finger tap confirmed - launching menu", cfg.finger_count);
-            if tx.send(GestureMessage::ShowPieMenu).is_err() {
+            if tx.send(GestureMessage::ShowPieMenu(cfg.tap_menu.clone())).is_err() {
                 return;
             }
+            if cfg.touchpad_select {
+                state = GestureState::Selecting { since: Instant::now(), tracker: None, pointed: None };
+            }
         }
+        check_selecting_timeout(&mut state);
 
         // Clear devices if rescan needed (outside the borrow)
         if needs_rescan {
@@ -1060,7 +1287,7 @@ fn gesture_loop(tx: Sender<GestureMessage>, config: SharedConfig) {
 /// Start the gesture detection thread with configurable parameters
 ///
 /// Returns an error if no touchpad devices are found or if permission is denied.
-/// The gesture detection runs in a background thread and sends `TrayMessage::ShowPieMenu`
+/// The gesture detection runs in a background thread and sends `GestureMessage::ShowPieMenu`
 /// when a multi-finger tap is detected.
 ///
 /// The `config` parameter provides shared configuration that can be updated at runtime
@@ -1125,3 +1352,75 @@ pub fn start_gesture_thread(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evdev::{EventType, InputEvent};
+
+    fn key(key: Key, value: i32) -> InputEvent {
+        InputEvent::new(EventType::KEY, key.code(), value)
+    }
+
+    fn abs(axis: AbsoluteAxisType, value: i32) -> InputEvent {
+        InputEvent::new(EventType::ABSOLUTE, axis.0, value)
+    }
+
+    /// Fingers in `slots` touching at x = 1000, then all moving by `dx`
+    fn drag(slots: i32, dx: i32) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        for step in [0, dx / 2, dx] {
+            for slot in 0..slots {
+                events.push(abs(AbsoluteAxisType::ABS_MT_SLOT, slot));
+                events.push(abs(AbsoluteAxisType::ABS_MT_TRACKING_ID, slot));
+                events.push(abs(AbsoluteAxisType::ABS_MT_POSITION_X, 1000 + step));
+                events.push(abs(AbsoluteAxisType::ABS_MT_POSITION_Y, 500 + slot * 100));
+            }
+        }
+        events
+    }
+
+    /// Run events through a 4-finger state machine, keeping what it reported
+    fn feed(state: &mut GestureState, events: &[InputEvent]) -> Vec<GestureEvent> {
+        events
+            .iter()
+            .map(|event| process_event(event, state, 4, Duration::from_millis(250), 100, 300, false))
+            .filter(|event| *event != GestureEvent::None)
+            .collect()
+    }
+
+    fn selecting() -> GestureState {
+        GestureState::Selecting { since: Instant::now(), tracker: None, pointed: None }
+    }
+
+    #[test]
+    fn test_touchpad_select_two_finger_drag() {
+        // Moving the pointer with one finger after the tap selects nothing
+        let mut state = selecting();
+        let mut events = vec![key(Key::BTN_TOUCH, 1), key(Key::BTN_TOOL_FINGER, 1)];
+        events.extend(drag(1, 600));
+        events.extend([key(Key::BTN_TOUCH, 0), key(Key::BTN_TOOL_FINGER, 0)]);
+        assert_eq!(feed(&mut state, &events), []);
+        assert!(matches!(state, GestureState::Selecting { tracker: None, .. }));
+
+        // Two fingers dragging right point at and pick the slice there
+        let mut events = vec![key(Key::BTN_TOUCH, 1), key(Key::BTN_TOOL_DOUBLETAP, 1)];
+        events.extend(drag(2, 600));
+        events.extend([key(Key::BTN_TOUCH, 0), key(Key::BTN_TOOL_DOUBLETAP, 0)]);
+        assert_eq!(feed(&mut state, &events), [GestureEvent::Pointing(0.0), GestureEvent::Selected(0.0)]);
+        assert!(matches!(state, GestureState::Idle));
+    }
+
+    #[test]
+    fn test_touchpad_select_leaves_taps_and_swipes() {
+        // The tap's fingers coming down again start a new gesture, so a swipe stays a swipe
+        let mut state = selecting();
+        let mut events = vec![key(Key::BTN_TOUCH, 1), key(Key::BTN_TOOL_QUADTAP, 1)];
+        events.extend(drag(4, 600));
+        assert_eq!(
+            feed(&mut state, &events),
+            [GestureEvent::FingersDown, GestureEvent::SwipeDetected(SwipeDirection::Right)]
+        );
+        assert!(matches!(state, GestureState::Idle));
+    }
+}
@@ -0,0 +1,217 @@
+//! Launch history
+//!
+//! Every item launched from the pie is counted in
+//! `~/.local/state/cosmic-pie-menu/history.json`, together with when it was
+//! last launched. With smart ordering on, the dock mirror is sorted by the
+//! frecency of its apps (see `apps::frecency`), and apps that are launched
+//! often without being in the dock can be offered in a "suggested" section.
+//!
+//! `running.json` next to it remembers when each running app was first seen,
+//! so running apps that aren't favorites keep their places between opens.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+/// Launch count at which all counts are halved, so old habits fade out
+const MAX_TOTAL_LAUNCHES: u32 = 1000;
+
+/// Running apps not seen for this long (seconds) lose their place
+const FORGET_RUNNING_AFTER: u64 = 30 * 24 * 60 * 60;
+
+/// How stale (seconds) a running app's last-seen time gets before it's
+/// refreshed, so the file isn't rewritten on every open
+const REFRESH_LAST_SEEN_AFTER: u64 = 24 * 60 * 60;
+
+/// How often one item was launched, and when it was last launched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    /// Number of launches (halved whenever the history grows too large)
+    pub count: u32,
+    /// Time of the last launch, in seconds since the Unix epoch
+    pub last_used: u64,
+}
+
+/// Launch records by item ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LaunchHistory {
+    pub items: BTreeMap<String, LaunchRecord>,
+}
+
+/// Current time in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Get the path to a file in the state directory
+fn state_path(file: &str) -> PathBuf {
+    dirs::state_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("cosmic-pie-menu")
+        .join(file)
+}
+
+/// Load a state file, or start empty if there is none
+fn load_state<T: Default + for<'de> Deserialize<'de>>(file: &str) -> T {
+    fs::read_to_string(state_path(file))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Save a state file
+fn save_state<T: Serialize>(file: &str, value: &T) -> std::io::Result<()> {
+    let path = state_path(file);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string(value)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    // Write then rename, so a pie closing mid-write can't corrupt the file
+    let tmp = path.with_extension(format!("json.{}", std::process::id()));
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, path)
+}
+
+impl LaunchHistory {
+    /// Load the history from disk, or start empty if there is none
+    pub fn load() -> Self {
+        load_state("history.json")
+    }
+
+    /// Count a launch of `id` at time `now`
+    pub fn record(&mut self, id: &str, now: u64) {
+        let record = self
+            .items
+            .entry(id.to_string())
+            .or_insert(LaunchRecord { count: 0, last_used: now });
+        record.count += 1;
+        record.last_used = now;
+
+        // Age the history: halve every count, forgetting items that drop to zero
+        let total: u32 = self.items.values().map(|r| r.count).sum();
+        if total > MAX_TOTAL_LAUNCHES {
+            self.items.retain(|_, record| {
+                record.count /= 2;
+                record.count > 0
+            });
+        }
+    }
+
+    /// Look up the record for an item
+    pub fn get(&self, id: &str) -> Option<&LaunchRecord> {
+        self.items.get(id)
+    }
+}
+
+/// Count a launch of `id` in the history file
+pub fn record_launch(id: &str) {
+    let mut history = LaunchHistory::load();
+    history.record(id, now());
+    if let Err(e) = save_state("history.json", &history) {
+        eprintln!("Failed to save launch history: {}", e);
+    }
+}
+
+/// When a running app was first and last seen, in seconds since the Unix epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeenRecord {
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+/// First-seen times of running apps by app ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunningOrder {
+    pub apps: BTreeMap<String, SeenRecord>,
+}
+
+impl RunningOrder {
+    /// Load the first-seen times from disk, or start empty if there are none
+    pub fn load() -> Self {
+        load_state("running.json")
+    }
+
+    /// Save the first-seen times to disk
+    pub fn save(&self) -> std::io::Result<()> {
+        save_state("running.json", self)
+    }
+
+    /// Note that `ids` are running at time `now`
+    ///
+    /// New apps are first seen now; apps not seen for a long time are forgotten,
+    /// so they go to the end when they come back. Returns whether anything
+    /// changed that needs saving.
+    pub fn update<'a>(&mut self, ids: impl IntoIterator<Item = &'a str>, now: u64) -> bool {
+        let before = self.apps.len();
+        self.apps.retain(|_, seen| now.saturating_sub(seen.last_seen) < FORGET_RUNNING_AFTER);
+        let mut changed = self.apps.len() != before;
+        for id in ids {
+            match self.apps.get_mut(id) {
+                Some(seen) => {
+                    if now.saturating_sub(seen.last_seen) >= REFRESH_LAST_SEEN_AFTER {
+                        seen.last_seen = now;
+                        changed = true;
+                    }
+                }
+                None => {
+                    self.apps.insert(id.to_string(), SeenRecord { first_seen: now, last_seen: now });
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// When an app was first seen running (`None` if never)
+    pub fn first_seen(&self, id: &str) -> Option<u64> {
+        self.apps.get(id).map(|seen| seen.first_seen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_ages_history() {
+        let mut history = LaunchHistory::default();
+        history.record("firefox", 100);
+        history.record("firefox", 200);
+        assert_eq!(history.get("firefox"), Some(&LaunchRecord { count: 2, last_used: 200 }));
+
+        history.record("rare", 300);
+        history.items.get_mut("firefox").unwrap().count = MAX_TOTAL_LAUNCHES;
+        history.record("firefox", 400);
+        assert_eq!(history.get("firefox").map(|r| r.count), Some(MAX_TOTAL_LAUNCHES / 2));
+        assert_eq!(history.get("rare"), None);
+    }
+
+    #[test]
+    fn test_running_order_forgets_old_apps() {
+        let day = REFRESH_LAST_SEEN_AFTER;
+        let mut order = RunningOrder::default();
+        assert!(order.update(["firefox"], 100));
+        assert!(order.update(["firefox", "gimp"], 100 + day));
+        // Nothing new and nothing stale: no need to save
+        assert!(!order.update(["firefox", "gimp"], 200 + day));
+        assert_eq!(order.first_seen("firefox"), Some(100));
+        assert_eq!(order.first_seen("gimp"), Some(100 + day));
+
+        let later = 200 + FORGET_RUNNING_AFTER;
+        assert!(order.update(["firefox"], later));
+        assert_eq!(order.first_seen("firefox"), Some(100));
+        assert_eq!(order.first_seen("gimp"), Some(100 + day));
+
+        let much_later = later + FORGET_RUNNING_AFTER;
+        assert!(order.update(["gimp"], much_later));
+        assert_eq!(order.first_seen("firefox"), None);
+        assert_eq!(order.first_seen("gimp"), Some(much_later));
+    }
+}
@@ -4,14 +4,23 @@
 //!
 //! Features:
 //! - Reads favorites from COSMIC dock config
+//! - Optional user-defined menu file instead of the dock mirror
 //! - Displays apps in a radial/pie layout
+//! - Window switcher mode with one slice per open window
+//! - Optional ordering by how often and how recently apps are launched
+//! - Optional long-lived pie service (`--daemon`) so pies open instantly
 //! - Size scales with number of apps
 //! - COSMIC panel applet for quick access and settings
 
 mod applet;
 mod apps;
 mod config;
+mod daemon;
+mod dbus;
+mod desktop_index;
 mod gesture;
+mod history;
+mod menus;
 mod pie_menu;
 mod settings;
 mod settings_cli;
@@ -52,10 +61,67 @@ fn query_running_via_subprocess() -> HashMap<String, u32> {
     }
 }
 
-/// Load all apps for the pie menu: dock applets first, then favorites, then running
-fn load_all_pie_apps() -> Vec<apps::AppInfo> {
-    let favorites = config::read_favorites();
+/// Load all apps for the named pie menu
+/// Uses the user's menu definition file if present and valid, otherwise mirrors the dock.
+/// An unknown menu name falls back to the default menu.
+/// Apps listed in configured groups are folded into submenu slices
+fn load_all_pie_apps(menu_name: &str) -> Vec<apps::AppInfo> {
+    if menu_name == menus::WINDOWS_MENU {
+        let items = apps::load_window_items(windows::query_windows_via_subprocess());
+        println!("Loaded {} open windows", items.len());
+        return items;
+    }
+
+    let pie_config = config::PieMenuConfig::load();
     let running = query_running_via_subprocess();
+
+    let all_apps = match menus::load_menu_file() {
+        Ok(Some(menu)) => {
+            let errors = menus::validate(&menu);
+            if errors.is_empty() {
+                let defs = menu.menu(menu_name).or_else(|| {
+                    if menu_name != menus::DEFAULT_MENU {
+                        eprintln!("No menu named '{}', using the default menu", menu_name);
+                    }
+                    menu.menu(menus::DEFAULT_MENU)
+                });
+                match defs {
+                    Some(defs) => {
+                        let resolved = apps::resolve_running(&running);
+                        let items = menus::build_items(defs, &resolved, &mut || load_dock_apps(&running, &pie_config));
+                        println!("Loaded {} items from {}", items.len(), menus::menu_path().display());
+                        items
+                    }
+                    None => load_dock_apps(&running, &pie_config),
+                }
+            } else {
+                eprintln!("Menu definition has errors, falling back to the dock:");
+                for error in &errors {
+                    eprintln!("  {}", error);
+                }
+                load_dock_apps(&running, &pie_config)
+            }
+        }
+        Ok(None) => {
+            if menu_name != menus::DEFAULT_MENU {
+                eprintln!("No menu named '{}' (no {}), mirroring the dock", menu_name, menus::menu_path().display());
+            }
+            load_dock_apps(&running, &pie_config)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("Falling back to the dock");
+            load_dock_apps(&running, &pie_config)
+        }
+    };
+
+    apps::apply_groups(all_apps, &pie_config.groups)
+}
+
+/// Load the dock mirror: dock applets first, then favorites, then running
+/// (favorites by frecency with smart ordering), then any suggested apps
+fn load_dock_apps(running: &HashMap<String, u32>, pie_config: &config::PieMenuConfig) -> Vec<apps::AppInfo> {
+    let favorites = config::read_favorites();
     let dock_applets = config::read_dock_applets();
 
     // Start with dock applets (App Library, Launcher, Workspaces)
@@ -63,12 +129,28 @@ fn load_all_pie_apps() -> Vec<apps::AppInfo> {
     let applet_count = all_apps.len();
 
     // Add favorites and running apps
-    let favorite_apps = apps::load_apps_with_running(&favorites, &running);
+    let favorite_apps = apps::load_apps_with_running(&favorites, running, pie_config.running_placement);
     let app_count = favorite_apps.len();
     all_apps.extend(favorite_apps);
 
     println!("Loaded {} dock applets + {} apps", applet_count, app_count);
 
+    if !pie_config.smart_order && pie_config.suggested_count == 0 {
+        return all_apps;
+    }
+    let history = history::LaunchHistory::load();
+    let now = history::now();
+    if pie_config.smart_order {
+        apps::sort_by_frecency(&mut all_apps, &history, now);
+    }
+    // Often-launched apps from outside the dock get their own section
+    let suggested = apps::suggested_apps(&history, &all_apps, pie_config.suggested_count, now);
+    if !suggested.is_empty() {
+        println!("Suggesting {} apps", suggested.len());
+        all_apps.push(apps::AppInfo::separator());
+        all_apps.extend(suggested);
+    }
+
     all_apps
 }
 
@@ -90,31 +172,72 @@ fn try_subprocess_lock(name: &str) -> Option<std::fs::File> {
 fn main() -> cosmic::iced::Result {
     let args: Vec<String> = std::env::args().collect();
 
-    // Internal: --pie-at X Y, show the pie menu at a specific position (used by gesture system)
+    // --menu NAME selects which menu the pie subprocesses show
+    // --windows is shorthand for --track --menu windows (the window switcher)
+    let menu_name = if args.contains(&"--windows".to_string()) {
+        menus::WINDOWS_MENU.to_string()
+    } else {
+        args.iter()
+            .position(|a| a == "--menu")
+            .and_then(|pos| args.get(pos + 1))
+            .cloned()
+            .unwrap_or_else(|| menus::DEFAULT_MENU.to_string())
+    };
+
+    // Internal: --pie-at X Y [--menu NAME], show the pie menu at a specific position (used by gesture system)
     if let Some(pos) = args.iter().position(|a| a == "--pie-at") {
         if args.len() > pos + 2 {
+            let x: f32 = args[pos + 1].parse().unwrap_or(0.0);
+            let y: f32 = args[pos + 2].parse().unwrap_or(0.0);
+            // Hand over to the pie service if it's running
+            if daemon::send(&daemon::Request::ShowAt { x, y, menu: menu_name.clone() }).is_ok() {
+                return Ok(());
+            }
             // Singleton: only one pie menu at a time
             let _lock = match try_subprocess_lock("pie") {
                 Some(f) => f,
                 None => return Ok(()),
             };
-            let x: f32 = args[pos + 1].parse().unwrap_or(0.0);
-            let y: f32 = args[pos + 2].parse().unwrap_or(0.0);
-            let apps = load_all_pie_apps();
+            let apps = load_all_pie_apps(&menu_name);
+            if apps.is_empty() && menu_name == menus::WINDOWS_MENU {
+                println!("No open windows");
+                return Ok(());
+            }
             pie_menu::show_pie_menu_at(apps, Some((x, y)));
             return Ok(());
         }
     }
 
-    // Internal: --track flag, use cursor tracking to position the menu (used by gesture system)
-    if args.contains(&"--track".to_string()) {
+    // Internal: --track [--menu NAME], use cursor tracking to position the menu (used by gesture system)
+    if args.contains(&"--track".to_string()) || args.contains(&"--windows".to_string()) {
+        // Hand over to the pie service if it's running
+        if daemon::send(&daemon::Request::Show { menu: menu_name.clone() }).is_ok() {
+            return Ok(());
+        }
         // Singleton: only one tracker at a time (prevents duplicates on multi-monitor)
         let _lock = match try_subprocess_lock("track") {
             Some(f) => f,
             None => return Ok(()),
         };
-        let apps = load_all_pie_apps();
-        pie_menu::show_pie_menu_with_tracking(apps);
+        pie_menu::show_pie_menu_with_tracking(menu_name);
+        return Ok(());
+    }
+
+    // --daemon: run the pie service, which opens pies on request (see daemon.rs)
+    if args.contains(&"--daemon".to_string()) {
+        // Singleton: a second service would steal the socket
+        let _lock = match try_subprocess_lock("daemon") {
+            Some(f) => f,
+            None => {
+                println!("Pie service already running");
+                return Ok(());
+            }
+        };
+        // The service outlives app installs, so keep its desktop file index current
+        if let Err(e) = desktop_index::start_watcher() {
+            eprintln!("Desktop file watching not available: {}", e);
+        }
+        pie_menu::run_pie_service(load_all_pie_apps);
         return Ok(());
     }
 
@@ -162,6 +285,46 @@ fn main() -> cosmic::iced::Result {
         return Ok(());
     }
 
+    // --check-menu: validate the menu definition file and report problems
+    if args.contains(&"--check-menu".to_string()) {
+        let path = menus::menu_path();
+        let pie_config = config::PieMenuConfig::load();
+        match menus::load_menu_file() {
+            Ok(Some(menu)) => {
+                for warning in menus::missing_apps(&menu) {
+                    eprintln!("warning: {}", warning);
+                }
+                let mut errors = menus::validate(&menu);
+                errors.extend(menus::validate_bindings(Some(&menu), &pie_config));
+                errors.extend(menus::validate_pins(&pie_config));
+                if errors.is_empty() {
+                    println!("{}: OK (menus: {})", path.display(), menu.names().join(", "));
+                } else {
+                    for error in &errors {
+                        eprintln!("{}", error);
+                    }
+                    std::process::exit(1);
+                }
+            }
+            Ok(None) => {
+                println!("{} not found; the pie mirrors the dock", path.display());
+                let mut errors = menus::validate_bindings(None, &pie_config);
+                errors.extend(menus::validate_pins(&pie_config));
+                if !errors.is_empty() {
+                    for error in &errors {
+                        eprintln!("{}", error);
+                    }
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     // Internal: --query-running just prints running apps and exits (for subprocess use)
     // Output format: app_id:count (one per line)
     if args.contains(&"--query-running".to_string()) {
@@ -172,6 +335,23 @@ fn main() -> cosmic::iced::Result {
         return Ok(());
     }
 
+    // Internal: --query-windows prints open windows and exits (for subprocess use)
+    // Output format: identifier<TAB>app_id<TAB>title (one per line)
+    if args.contains(&"--query-windows".to_string()) {
+        for window in windows::get_windows() {
+            println!("{}", windows::format_window_line(&window));
+        }
+        return Ok(());
+    }
+
+    // Internal: --query-workspaces prints workspace names and exits (for subprocess use)
+    if args.contains(&"--query-workspaces".to_string()) {
+        for name in windows::get_workspaces() {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+
     // Default: run as COSMIC panel applet
     println!("COSMIC Pie Menu starting as panel applet...");
     applet::run_applet()
@@ -0,0 +1,540 @@
+//! User-defined pie menu definitions
+//!
+//! Lets the pie show an explicit list of items instead of mirroring the dock.
+//! The definition lives in `menu.json` next to the main config file:
+//!
+//! ```json
+//! {
+//!   "items": [
+//!     { "type": "app", "id": "firefox" },
+//!     { "type": "command", "name": "System Monitor", "exec": "cosmic-term -e htop" },
+//!     { "type": "open", "target": "~/Projects" },
+//!     { "type": "builtin", "action": "lock_screen" },
+//!     { "type": "separator" },
+//!     { "type": "submenu", "name": "Dev tools", "items": [ { "type": "app", "id": "code" } ] },
+//!     { "type": "dock" }
+//!   ],
+//!   "menus": {
+//!     "media": [ { "type": "app", "id": "org.gnome.Rhythmbox3" } ]
+//!   }
+//! }
+//! ```
+//!
+//! The top-level `items` form the "default" menu; each entry of `menus` is a
+//! further named menu that a trigger can be bound to (see the `*_menu` fields
+//! of [`PieMenuConfig`]). The "windows" menu is built in: it shows every open
+//! window.
+//!
+//! The file is validated on load; if it has errors they are reported and the
+//! pie falls back to mirroring the dock.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::apps::{self, AppInfo, BuiltinAction};
+use crate::config::{self, PieMenuConfig};
+
+/// A single item in a menu definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MenuItemDef {
+    /// An installed application, by desktop ID
+    App {
+        /// Desktop file name without .desktop
+        id: String,
+        /// Override the display name from the desktop file
+        #[serde(default)]
+        name: Option<String>,
+        /// Override the icon from the desktop file
+        #[serde(default)]
+        icon: Option<String>,
+    },
+    /// An arbitrary shell command
+    Command {
+        /// Display name
+        name: String,
+        /// Command line, run through `sh -c`
+        exec: String,
+        /// Icon name or path
+        #[serde(default)]
+        icon: Option<String>,
+    },
+    /// A URL, file or folder opened with its default handler
+    Open {
+        /// URL, absolute path, or path starting with `~/`
+        target: String,
+        /// Display name (defaults to the file name or URL)
+        #[serde(default)]
+        name: Option<String>,
+        /// Icon name or path (defaults by kind of target)
+        #[serde(default)]
+        icon: Option<String>,
+    },
+    /// An action provided by the pie menu itself
+    Builtin {
+        /// Which action
+        action: BuiltinAction,
+        /// Override the default name
+        #[serde(default)]
+        name: Option<String>,
+        /// Override the default icon
+        #[serde(default)]
+        icon: Option<String>,
+    },
+    /// An empty gap in the ring
+    Separator,
+    /// A nested pie of further items
+    Submenu {
+        /// Display name
+        name: String,
+        /// Icon name or path
+        #[serde(default)]
+        icon: Option<String>,
+        /// Items in the nested pie
+        items: Vec<MenuItemDef>,
+    },
+    /// The dock mirror: dock applets, favorites and running apps
+    Dock,
+}
+
+/// Name of the menu made of the top-level `items`
+pub const DEFAULT_MENU: &str = "default";
+
+/// Name of the built-in window switcher menu: one slice per open window
+pub const WINDOWS_MENU: &str = "windows";
+
+/// Contents of the menu definition file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MenuFile {
+    /// Items of the default pie, in clockwise order starting at the top
+    #[serde(default)]
+    pub items: Vec<MenuItemDef>,
+    /// Additional named menus, keyed by name
+    #[serde(default)]
+    pub menus: BTreeMap<String, Vec<MenuItemDef>>,
+}
+
+impl MenuFile {
+    /// Get the items of a menu by name
+    ///
+    /// Returns `None` if there is no such menu. The default menu is only
+    /// present when the file has top-level items.
+    pub fn menu(&self, name: &str) -> Option<&[MenuItemDef]> {
+        if name == DEFAULT_MENU {
+            (!self.items.is_empty()).then_some(self.items.as_slice())
+        } else {
+            self.menus.get(name).map(Vec::as_slice)
+        }
+    }
+
+    /// Names of all menus defined in the file
+    pub fn names(&self) -> Vec<&str> {
+        let default = (!self.items.is_empty()).then_some(DEFAULT_MENU);
+        default
+            .into_iter()
+            .chain(self.menus.keys().map(String::as_str))
+            .collect()
+    }
+}
+
+/// Errors found while loading a menu definition
+#[derive(Debug)]
+pub enum MenuError {
+    /// The file exists but couldn't be read
+    Io(PathBuf, std::io::Error),
+    /// The file isn't valid JSON or doesn't match the menu format
+    Parse(PathBuf, serde_json::Error),
+    /// An item is well-formed but can't be used
+    Invalid {
+        /// Where the item is, e.g. `items[2].items[0]`
+        location: String,
+        /// What is wrong with it
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for MenuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "Could not read {}: {}", path.display(), e),
+            Self::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Invalid { location, reason } => write!(f, "{}: {}", location, reason),
+        }
+    }
+}
+
+/// Get the path to the menu definition file (next to config.json)
+pub fn menu_path() -> PathBuf {
+    PieMenuConfig::config_path().with_file_name("menu.json")
+}
+
+/// Parse a menu definition from JSON text
+pub fn parse_menu(content: &str) -> Result<MenuFile, serde_json::Error> {
+    serde_json::from_str(content)
+}
+
+/// Load the menu definition file
+///
+/// Returns `Ok(None)` if no definition file exists (the pie mirrors the dock).
+pub fn load_menu_file() -> Result<Option<MenuFile>, MenuError> {
+    let path = menu_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| MenuError::Io(path.clone(), e))?;
+    parse_menu(&content)
+        .map(Some)
+        .map_err(|e| MenuError::Parse(path, e))
+}
+
+/// Menu names a trigger can be bound to
+///
+/// Every menu from the definition file plus the built-in ones, followed by
+/// any configured name that no longer exists so the current value still shows.
+pub fn menu_choices(config: &PieMenuConfig) -> Vec<String> {
+    let mut names: Vec<String> = match load_menu_file() {
+        Ok(Some(menu)) => menu.names().into_iter().map(String::from).collect(),
+        _ => Vec::new(),
+    };
+    if !names.iter().any(|n| n == DEFAULT_MENU) {
+        names.insert(0, DEFAULT_MENU.to_string());
+    }
+    names.push(WINDOWS_MENU.to_string());
+    for (_, name) in config.menu_bindings() {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Check every item in a menu definition, returning all problems found
+pub fn validate(menu: &MenuFile) -> Vec<MenuError> {
+    let mut errors = Vec::new();
+
+    if menu.items.is_empty() && menu.menus.is_empty() {
+        errors.push(MenuError::Invalid {
+            location: "items".to_string(),
+            reason: "menu has no items".to_string(),
+        });
+    }
+    validate_items(&menu.items, "items", &mut false, &mut errors);
+
+    for (name, items) in &menu.menus {
+        let location = format!("menus.{}", name);
+        if name.trim().is_empty() {
+            errors.push(MenuError::Invalid {
+                location: location.clone(),
+                reason: "menu name is empty".to_string(),
+            });
+        } else if name == DEFAULT_MENU {
+            errors.push(MenuError::Invalid {
+                location: location.clone(),
+                reason: format!("'{}' is reserved for the top-level items", DEFAULT_MENU),
+            });
+        } else if name == WINDOWS_MENU {
+            errors.push(MenuError::Invalid {
+                location: location.clone(),
+                reason: format!("'{}' is reserved for the built-in window switcher", WINDOWS_MENU),
+            });
+        }
+        if items.is_empty() {
+            errors.push(MenuError::Invalid {
+                location: location.clone(),
+                reason: "menu has no items".to_string(),
+            });
+        }
+        validate_items(items, &location, &mut false, &mut errors);
+    }
+
+    errors
+}
+
+/// Find app items whose desktop file is missing
+///
+/// These aren't errors: an uninstalled app shouldn't cost the whole menu, so
+/// the pie skips such items (see [`build_items`]) and these are warnings.
+pub fn missing_apps(menu: &MenuFile) -> Vec<MenuError> {
+    let mut missing = Vec::new();
+    missing_in(&menu.items, "items", &mut missing);
+    for (name, items) in &menu.menus {
+        missing_in(items, &format!("menus.{}", name), &mut missing);
+    }
+    missing
+}
+
+fn missing_in(items: &[MenuItemDef], location: &str, missing: &mut Vec<MenuError>) {
+    for (i, item) in items.iter().enumerate() {
+        let here = format!("{}[{}]", location, i);
+        match item {
+            MenuItemDef::App { id, .. } if !id.trim().is_empty() && apps::load_app_info(id).is_none() => {
+                missing.push(MenuError::Invalid {
+                    location: here,
+                    reason: format!("no desktop file found for app id '{}', skipping it", id),
+                });
+            }
+            MenuItemDef::Submenu { items, .. } => missing_in(items, &format!("{}.items", here), missing),
+            _ => {}
+        }
+    }
+}
+
+/// Check that every trigger in the config opens a menu that exists
+///
+/// The default menu always exists: without top-level items it mirrors the dock.
+/// So does the built-in windows menu.
+pub fn validate_bindings(menu: Option<&MenuFile>, config: &PieMenuConfig) -> Vec<MenuError> {
+    config
+        .menu_bindings()
+        .into_iter()
+        .filter(|(_, name)| {
+            *name != DEFAULT_MENU
+                && *name != WINDOWS_MENU
+                && menu.is_none_or(|m| !m.menus.contains_key(*name))
+        })
+        .map(|(key, name)| MenuError::Invalid {
+            location: format!("config.{}", key),
+            reason: format!("no menu named '{}'", name),
+        })
+        .collect()
+}
+
+/// Check that every pinned direction in the config can be read
+pub fn validate_pins(config: &PieMenuConfig) -> Vec<MenuError> {
+    config
+        .pinned_directions
+        .iter()
+        .filter(|(_, direction)| config::pin_angle(direction).is_none())
+        .map(|(id, direction)| MenuError::Invalid {
+            location: format!("config.pinned_directions.{}", id),
+            reason: format!("'{}' is not a compass direction or angle in degrees", direction),
+        })
+        .collect()
+}
+
+fn validate_items(
+    items: &[MenuItemDef],
+    location: &str,
+    dock_seen: &mut bool,
+    errors: &mut Vec<MenuError>,
+) {
+    for (i, item) in items.iter().enumerate() {
+        let here = format!("{}[{}]", location, i);
+        let mut invalid = |reason: String| {
+            errors.push(MenuError::Invalid {
+                location: here.clone(),
+                reason,
+            });
+        };
+
+        match item {
+            MenuItemDef::App { id, .. } => {
+                if id.trim().is_empty() {
+                    invalid("app id is empty".to_string());
+                }
+            }
+            MenuItemDef::Command { name, exec, .. } => {
+                if name.trim().is_empty() {
+                    invalid("command name is empty".to_string());
+                }
+                if exec.trim().is_empty() {
+                    invalid(format!("command '{}' has an empty exec", name));
+                }
+            }
+            MenuItemDef::Open { target, .. } => {
+                let target = target.trim();
+                if target.is_empty() {
+                    invalid("open target is empty".to_string());
+                } else if !apps::is_url(target)
+                    && !target.starts_with('/')
+                    && !target.starts_with('~')
+                    && !target.starts_with("file://")
+                {
+                    invalid(format!("'{}' is neither a URL nor an absolute path", target));
+                }
+            }
+            MenuItemDef::Builtin { .. } | MenuItemDef::Separator => {}
+            MenuItemDef::Submenu { name, items, .. } => {
+                if name.trim().is_empty() {
+                    invalid("submenu name is empty".to_string());
+                }
+                if items.is_empty() {
+                    invalid(format!("submenu '{}' has no items", name));
+                }
+                validate_items(items, &format!("{}.items", here), dock_seen, errors);
+            }
+            MenuItemDef::Dock => {
+                if *dock_seen {
+                    invalid("the dock can only be included once".to_string());
+                }
+                *dock_seen = true;
+            }
+        }
+    }
+}
+
+/// Resolve menu item definitions into pie items
+///
+/// `dock` is called to produce the dock mirror when a `dock` item is present.
+pub fn build_items(
+    defs: &[MenuItemDef],
+    running_apps: &[(apps::ResolvedId, u32)],
+    dock: &mut dyn FnMut() -> Vec<AppInfo>,
+) -> Vec<AppInfo> {
+    let mut items = Vec::new();
+
+    for def in defs {
+        match def {
+            MenuItemDef::App { id, name, icon } => {
+                if let Some(mut app) = apps::load_app_info(id) {
+                    app.is_favorite = true;
+                    app.running_count = apps::get_running_count(id, running_apps);
+                    if let Some(name) = name {
+                        app.name = name.clone();
+                    }
+                    if icon.is_some() {
+                        app.icon = icon.clone();
+                    }
+                    items.push(app);
+                } else {
+                    eprintln!("Skipping app '{}': no desktop file found", id);
+                }
+            }
+            MenuItemDef::Command { name, exec, icon } => {
+                items.push(AppInfo::command(name, exec, icon.clone()));
+            }
+            MenuItemDef::Open { target, name, icon } => {
+                items.push(AppInfo::open(name.clone(), target.trim(), icon.clone()));
+            }
+            MenuItemDef::Builtin { action, name, icon } => {
+                items.push(AppInfo::builtin(*action, name.clone(), icon.clone()));
+            }
+            MenuItemDef::Separator => items.push(AppInfo::separator()),
+            MenuItemDef::Submenu { name, icon, items: children } => {
+                let children = build_items(children, running_apps, dock);
+                items.push(AppInfo::submenu(name, icon.clone(), children));
+            }
+            MenuItemDef::Dock => items.extend(dock()),
+        }
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_menu() {
+        let menu = parse_menu(
+            r#"{
+                "items": [
+                    { "type": "command", "name": "Top", "exec": "cosmic-term -e htop" },
+                    { "type": "separator" },
+                    { "type": "submenu", "name": "More", "items": [ { "type": "dock" } ] },
+                    { "type": "builtin", "action": "lock_screen" }
+                ]
+            }"#,
+        )
+        .expect("valid menu");
+
+        assert_eq!(menu.items.len(), 4);
+        assert_eq!(menu.names(), [DEFAULT_MENU]);
+        assert_eq!(menu.items[1], MenuItemDef::Separator);
+        assert!(validate(&menu).is_empty());
+
+        // Unknown fields are reported rather than silently ignored
+        let typo = r#"{ "items": [ { "type": "command", "name": "Top", "exec": "htop", "icn": "x" } ] }"#;
+        assert!(parse_menu(typo).is_err());
+    }
+
+    #[test]
+    fn test_validate_menu() {
+        let menu = MenuFile {
+            items: vec![
+                MenuItemDef::Command {
+                    name: "Empty".to_string(),
+                    exec: " ".to_string(),
+                    icon: None,
+                },
+                MenuItemDef::Open {
+                    target: "Documents".to_string(),
+                    name: None,
+                    icon: None,
+                },
+                MenuItemDef::Submenu {
+                    name: "Nothing".to_string(),
+                    icon: None,
+                    items: vec![MenuItemDef::Dock, MenuItemDef::Dock],
+                },
+                MenuItemDef::App {
+                    id: "no-such-app-installed".to_string(),
+                    name: None,
+                    icon: None,
+                },
+            ],
+            menus: BTreeMap::from([("media".to_string(), Vec::new())]),
+        };
+
+        let errors: Vec<String> = validate(&menu).iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "items[0]: command 'Empty' has an empty exec",
+                "items[1]: 'Documents' is neither a URL nor an absolute path",
+                "items[2].items[1]: the dock can only be included once",
+                "menus.media: menu has no items",
+            ]
+        );
+
+        // A missing app is skipped with a warning, not an error
+        let warnings: Vec<String> = missing_apps(&menu).iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            warnings,
+            ["items[3]: no desktop file found for app id 'no-such-app-installed', skipping it"]
+        );
+    }
+
+    #[test]
+    fn test_named_menus() {
+        let menu = parse_menu(
+            r#"{
+                "menus": {
+                    "media": [ { "type": "command", "name": "Play", "exec": "playerctl play-pause" } ]
+                }
+            }"#,
+        )
+        .expect("valid menu");
+
+        assert!(validate(&menu).is_empty());
+        assert_eq!(menu.names(), ["media"]);
+        assert_eq!(menu.menu("media").map(<[_]>::len), Some(1));
+        // No top-level items: the default menu mirrors the dock
+        assert!(menu.menu(DEFAULT_MENU).is_none());
+
+        let config = PieMenuConfig {
+            tap_menu: "media".to_string(),
+            swipe_menu: WINDOWS_MENU.to_string(),
+            middle_click_menu: "windws".to_string(),
+            pinned_directions: BTreeMap::from([
+                ("firefox".to_string(), "north".to_string()),
+                ("gimp".to_string(), "nroth".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let errors: Vec<String> = validate_bindings(Some(&menu), &config)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(errors, ["config.middle_click_menu: no menu named 'windws'"]);
+        let errors: Vec<String> = validate_pins(&config).iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            ["config.pinned_directions.gimp: 'nroth' is not a compass direction or angle in degrees"]
+        );
+    }
+}
@@ -11,25 +11,36 @@ use cosmic::iced::widget::canvas::{Event, Geometry, Path, Program, Stroke, Text}
 use cosmic::iced::{Color, Font, Point, Rectangle, Renderer, Theme, mouse};
 use cosmic::iced::core::svg::{Handle as SvgHandle, Svg};
 use cosmic::iced::core::image::{Handle as ImageHandle, Image};
-use cosmic::iced::window::Id;
+use cosmic::iced::window::{self, Id};
 use cosmic::iced::{Element, Length, Task, Subscription};
 use cosmic::iced::alignment::{Horizontal, Vertical};
 use cosmic::iced::keyboard::{self, Key};
 use cosmic::iced::time;
 use cosmic::iced::platform_specific::runtime::wayland::layer_surface::SctkLayerSurfaceSettings;
 use cosmic::iced::platform_specific::shell::commands::layer_surface::{
-    get_layer_surface, Anchor, KeyboardInteractivity, Layer,
+    destroy_layer_surface, get_layer_surface, Anchor, KeyboardInteractivity, Layer,
 };
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::f32::consts::PI;
 use std::fs;
+use std::ops::Range;
 use std::path::PathBuf;
 use std::process::Command;
-use std::time::Duration;
-use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex, OnceLock};
 
-use crate::apps::{AppInfo, find_icon_path};
-use crate::config::PieMenuConfig;
-use crate::windows;
+use crate::apps::{self, AppInfo, ItemKind, find_icon_path};
+use crate::config::{self, DwellAction, PieMenuConfig};
+use crate::daemon;
+use crate::dbus;
+use crate::desktop_index;
+use crate::history;
+use crate::menus;
+use crate::windows::{self, WindowAction};
+
+/// Most results a search shows, so the ring stays a usable size
+const MAX_SEARCH_RESULTS: usize = 16;
 
 /// Minimum radius of the pie menu circle (for small number of apps)
 const MIN_MENU_RADIUS: f32 = 80.0;
@@ -71,6 +82,208 @@ fn calculate_icon_radius(menu_radius: f32, inner_radius: f32, _num_apps: usize)
     segment_inner + (segment_outer - segment_inner) * 0.65
 }
 
+/// Space between one ring's outer edge and the next ring's inner edge
+/// (room for the running indicators of the inner ring)
+const RING_GAP: f32 = 8.0;
+
+/// Depth of an outer ring beyond its icons
+const RING_PADDING: f32 = 24.0;
+
+/// One concentric ring of the pie: a run of consecutive slices between two radii
+#[derive(Clone)]
+struct Ring {
+    /// Indices of the ring's items (and slices)
+    items: Range<usize>,
+    /// Radius where the ring's segments start
+    inner_radius: f32,
+    /// Radius where the ring's segments end
+    outer_radius: f32,
+}
+
+/// Split items into up to `max_rings` concentric rings, innermost first
+///
+/// The user's own items (favorites, menu items) go on the inner rings and
+/// the rest (running non-favorites, suggestions) start a ring of their own.
+/// A ring holds `capacity` items and the rest overflow to the next ring out;
+/// the outermost ring takes whatever is left. Separators stay with the item
+/// before them, but not at either end of a ring.
+fn split_rings(apps: Vec<AppInfo>, max_rings: usize, capacity: usize) -> Vec<Vec<AppInfo>> {
+    let capacity = capacity.max(1);
+    let all_own = apps.iter().all(|app| app.is_favorite || app.is_separator());
+    if max_rings <= 1 || (all_own && apps.len() <= capacity) {
+        return vec![apps];
+    }
+
+    let mut own = Vec::new();
+    let mut others = Vec::new();
+    let mut last_own = true;
+    for app in apps {
+        if !app.is_separator() {
+            last_own = app.is_favorite;
+        }
+        if last_own { own.push(app) } else { others.push(app) }
+    }
+
+    let mut rings: Vec<Vec<AppInfo>> = Vec::new();
+    for mut group in [own, others] {
+        while let Some(pos) = group.iter().position(|app| !app.is_separator()) {
+            group.drain(..pos);
+            if rings.len() + 1 < max_rings && group.len() > capacity {
+                let rest = group.split_off(capacity);
+                rings.push(std::mem::replace(&mut group, rest));
+            } else if rings.len() < max_rings {
+                rings.push(std::mem::take(&mut group));
+            } else if let Some(outermost) = rings.last_mut() {
+                outermost.append(&mut group);
+            }
+        }
+    }
+    for ring in &mut rings {
+        while ring.last().is_some_and(|app| app.is_separator()) {
+            ring.pop();
+        }
+    }
+    if rings.is_empty() {
+        rings.push(Vec::new());
+    }
+    rings
+}
+
+/// Radii of rings holding `counts` items each over `arc` radians, innermost first
+///
+/// The inner ring is sized like a single-ring pie; each ring outside it is
+/// deep enough for its icons and wide enough around for its items. A pie
+/// fanned over part of the circle grows so its icons keep their spacing.
+fn build_rings(counts: &[usize], icon_size: u16, icon_spacing: f32, arc: f32) -> Vec<Ring> {
+    let icon_spacing = icon_spacing * 2.0 * PI / arc;
+    let mut rings: Vec<Ring> = Vec::new();
+    let mut start = 0;
+    for &count in counts {
+        let (inner_radius, outer_radius) = match rings.last() {
+            None => {
+                let menu_radius = calculate_menu_radius(count, icon_spacing);
+                (calculate_inner_radius(menu_radius), menu_radius)
+            }
+            Some(inside) => {
+                let inner = inside.outer_radius + RING_GAP;
+                (inner, (inner + icon_size as f32 + RING_PADDING).max(calculate_menu_radius(count, icon_spacing)))
+            }
+        };
+        rings.push(Ring { items: start..start + count, inner_radius, outer_radius });
+        start += count;
+    }
+    rings
+}
+
+/// Size of the square a pie needs: the ring plus its icons, name and margins
+fn menu_size(menu_radius: f32, icon_size: u16) -> f32 {
+    menu_radius * 2.0 + icon_size as f32 + 80.0
+}
+
+/// The arc a pie's slices are fanned over when it opens at a screen edge
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Fan {
+    /// Where the arc starts (radians, screen coordinates)
+    start: f32,
+    /// How far the arc runs clockwise from `start`
+    span: f32,
+}
+
+impl Fan {
+    /// Shrink an angle of the full-circle layout to the arc
+    fn scale(self, angle: f32) -> f32 {
+        angle * self.span / (2.0 * PI)
+    }
+
+    /// Map an angle of a full-circle ring whose first slice starts at `origin` onto the arc
+    fn to_screen(self, origin: f32, angle: f32) -> f32 {
+        self.start + self.scale((angle - origin).rem_euclid(2.0 * PI))
+    }
+
+    /// Map a direction on screen back to the full-circle ring (None off the arc)
+    fn to_layout(self, origin: f32, angle: f32) -> Option<f32> {
+        let offset = (angle - self.start).rem_euclid(2.0 * PI);
+        (offset <= self.span).then(|| origin + offset * 2.0 * PI / self.span)
+    }
+}
+
+/// The arc for a pie centered at (x, y) on a `width` x `height` screen, when
+/// the full pie would cross an edge
+///
+/// `reach(span)` is how far from its center the pie reaches when fanned over
+/// `span` radians (`2 * PI` for the full circle). At an edge the slices fan
+/// over the half facing into the screen, in a corner over the quarter. A
+/// fanned pie grows to keep its icons apart, so an edge fan that then reaches
+/// a neighbouring edge becomes a corner fan. None when the full pie fits, or
+/// when it would cross opposite edges (a screen too small for a fan to help).
+fn fan_at(x: f32, y: f32, width: f32, height: f32, reach: impl Fn(f32) -> f32) -> Option<Fan> {
+    let (mut left, mut right, mut top, mut bottom) = (false, false, false, false);
+    let mut span = 2.0 * PI;
+    loop {
+        let reach = reach(span);
+        left |= x < reach;
+        right |= x > width - reach;
+        top |= y < reach;
+        bottom |= y > height - reach;
+        if (left && right) || (top && bottom) {
+            return None;
+        }
+        let fan = edge_fan(left, right, top, bottom)?;
+        if fan.span == span {
+            return Some(fan);
+        }
+        span = fan.span;
+    }
+}
+
+/// The arc facing into the screen from the crossed edges (None if none are)
+fn edge_fan(left: bool, right: bool, top: bool, bottom: bool) -> Option<Fan> {
+    let (start, span) = match (left, right, top, bottom) {
+        (true, _, true, _) => (0.0, PI / 2.0),       // top-left: east to south
+        (_, true, true, _) => (PI / 2.0, PI / 2.0),  // top-right: south to west
+        (true, _, _, true) => (-PI / 2.0, PI / 2.0), // bottom-left: north to east
+        (_, true, _, true) => (PI, PI / 2.0),        // bottom-right: west to north
+        (true, _, _, _) => (-PI / 2.0, PI),          // left: north through east to south
+        (_, true, _, _) => (PI / 2.0, PI),           // right: south through west to north
+        (_, _, true, _) => (0.0, PI),                // top: east through south to west
+        (_, _, _, true) => (PI, PI),                 // bottom: west through north to east
+        _ => return None,
+    };
+    Some(Fan { start, span })
+}
+
+/// A circle, or just its arc for a fanned pie
+fn circle_or_arc(center: Point, radius: f32, fan: Option<Fan>) -> Path {
+    match fan {
+        None => Path::circle(center, radius),
+        Some(fan) => Path::new(|builder| {
+            let steps = 64;
+            builder.move_to(Point::new(
+                center.x + radius * fan.start.cos(),
+                center.y + radius * fan.start.sin(),
+            ));
+            for i in 1..=steps {
+                let angle = fan.start + fan.span * i as f32 / steps as f32;
+                builder.line_to(Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin()));
+            }
+        }),
+    }
+}
+
+/// The ring holding item `index`
+fn ring_of(rings: &[Ring], index: usize) -> Option<&Ring> {
+    rings.iter().find(|ring| ring.items.contains(&index))
+}
+
+/// The ring covering a distance from the center, for one between the
+/// inner and outer radius of the whole pie
+fn ring_at_distance(rings: &[Ring], distance: f32) -> Option<&Ring> {
+    rings
+        .iter()
+        .find(|ring| distance <= ring.outer_radius + RING_GAP / 2.0)
+        .or(rings.last())
+}
+
 /// Rubber band effect: radial offset targets for neighbors by distance from hovered icon
 /// Index 0 = hovered icon itself, 1 = immediate neighbor, 2 = next neighbor
 const RUBBER_BAND_RADIAL: &[f32] = &[1.0, 0.4, 0.12];
@@ -90,6 +303,15 @@ fn circular_distance(a: usize, b: usize, n: usize) -> usize {
     forward.min(backward)
 }
 
+/// Shortest distance between two items around their ring (None on different rings)
+fn ring_distance(rings: &[Ring], a: usize, b: usize) -> Option<usize> {
+    let ring = ring_of(rings, a)?;
+    let n = ring.items.len();
+    ring.items
+        .contains(&b)
+        .then(|| circular_distance(a - ring.items.start, b - ring.items.start, n))
+}
+
 /// Calculate direction from index `from` toward index `toward` in a ring of n elements
 /// Returns -1.0 if `toward` is counter-clockwise, +1.0 if clockwise, 0.0 if same
 fn circular_direction(from: usize, toward: usize, n: usize) -> f32 {
@@ -116,6 +338,8 @@ struct PieTheme {
     running_indicator_color: Color,
     /// Outer indicator ring background color
     indicator_ring_color: Color,
+    /// Dwell progress fill over the hovered segment
+    dwell_fill_color: Color,
 }
 
 /// Convert a COSMIC Srgba color to iced Color with custom alpha
@@ -158,6 +382,9 @@ impl PieTheme {
         // Outer indicator ring - use a darker shade of the background
         let indicator_ring_color = srgba_to_color(bg.base, 1.0);
 
+        // Dwell fill - text color, so it shows over the accent hover
+        let dwell_fill_color = srgba_to_color(bg.on, 0.3);
+
         Self {
             bg_color,
             segment_color,
@@ -166,6 +393,7 @@ impl PieTheme {
             text_color,
             running_indicator_color,
             indicator_ring_color,
+            dwell_fill_color,
         }
     }
 }
@@ -213,6 +441,97 @@ fn is_dark_mode() -> bool {
     true
 }
 
+/// What hosts a pie or tracker surface, which decides how it closes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Host {
+    /// Its own process (`--pie-at`/`--track`), which exits on close
+    Process,
+    /// The pie service (`--daemon`), which stays running with the surface removed
+    Service,
+}
+
+/// Spawn a launch command without leaving a zombie behind
+///
+/// The pie service outlives the apps it launches, so their exit status has to
+/// be collected.
+fn spawn_detached(command: &mut Command) -> std::io::Result<()> {
+    let mut child = command.spawn()?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Icon paths already looked up, by icon name and size
+type IconCache = HashMap<(String, u16), Option<PathBuf>>;
+
+/// Icon lookup walks the icon themes on disk, so the pie service keeps the
+/// results across opens.
+static ICON_CACHE: Mutex<Option<IconCache>> = Mutex::new(None);
+
+/// Look up an icon path, using the cache
+fn cached_icon_path(icon_name: &str, size: u16) -> Option<PathBuf> {
+    let mut cache = ICON_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .get_or_insert_with(HashMap::new)
+        .entry((icon_name.to_string(), size))
+        .or_insert_with(|| find_icon_path(icon_name, size))
+        .clone()
+}
+
+/// Forget cached icon paths, e.g. after the icon theme changed
+fn clear_icon_cache() {
+    *ICON_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// The next selectable item after `current` going round the ring, clockwise or
+/// anticlockwise; separators are skipped
+///
+/// With nothing highlighted, starts from the top of the ring.
+fn step_selection(apps: &[AppInfo], current: Option<usize>, clockwise: bool) -> Option<usize> {
+    let n = apps.len();
+    if n == 0 {
+        return None;
+    }
+    let start = current.unwrap_or(if clockwise { n - 1 } else { 0 });
+    (1..=n)
+        .map(|step| if clockwise { (start + step) % n } else { (start + n - step) % n })
+        .find(|&i| !apps[i].is_separator())
+}
+
+/// The index of the `n`th selectable item (from 0), skipping separators
+fn nth_selectable(apps: &[AppInfo], n: usize) -> Option<usize> {
+    apps.iter()
+        .enumerate()
+        .filter(|(_, app)| !app.is_separator())
+        .nth(n)
+        .map(|(i, _)| i)
+}
+
+/// The items matching a search query, including those inside submenus
+///
+/// Items whose name starts with the query come first; otherwise the ring's
+/// order is kept.
+fn search_items(apps: &[AppInfo], query: &str) -> Vec<AppInfo> {
+    fn collect(apps: &[AppInfo], query: &str, found: &mut Vec<AppInfo>) {
+        for app in apps {
+            if let ItemKind::Submenu(children) = &app.kind {
+                collect(children, query, found);
+            }
+            if !app.is_separator() && app.matches_query(query) && !found.iter().any(|f| f.id == app.id) {
+                found.push(app.clone());
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    collect(apps, query, &mut found);
+    let query = query.to_lowercase();
+    found.sort_by_key(|app| !app.name.to_lowercase().starts_with(&query));
+    found.truncate(MAX_SEARCH_RESULTS);
+    found
+}
+
 /// Messages for the pie menu
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -220,12 +539,42 @@ pub enum Message {
     LaunchApp(usize),
     /// Close the menu
     Close,
-    /// Key pressed
-    KeyPressed(Key),
+    /// Key pressed, with the modifiers held
+    KeyPressed(Key, keyboard::Modifiers),
     /// Canvas event
     CanvasEvent(PieCanvasMessage),
     /// Initial tick to force layout
     Tick,
+    /// Open the submenu at this index as a nested pie
+    OpenSubmenu(usize),
+    /// Return from a nested pie to its parent ring
+    Back,
+    /// The held trigger was let go (marking-menu mode), having moved in this
+    /// direction (radians) if it moved far enough
+    Release(Option<f32>),
+    /// A touchpad drag points in this direction (radians); highlight that slice
+    Point(f32),
+    /// The pie's surface has this size (width, height)
+    Resized(f32, f32),
+    /// The open windows of the item at `index` arrived, with the workspace
+    /// names for a context ring. `id` guards against the ring having changed
+    /// while they were queried.
+    WindowsLoaded {
+        index: usize,
+        id: String,
+        query: WindowQuery,
+        windows: Vec<windows::WindowInfo>,
+        workspaces: Vec<String>,
+    },
+}
+
+/// Why the windows of an item were queried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowQuery {
+    /// Switch to the app's window, or choose between several
+    Switch,
+    /// Build the right-click context ring
+    Context,
 }
 
 #[derive(Debug, Clone)]
@@ -301,6 +650,151 @@ struct AppSlice {
     end_angle: f32,       // End of slice
     running_count: u32,   // Number of running windows (0 = not running)
     glow_handle: Option<SvgHandle>, // Pre-created tinted glow handle
+    is_submenu: bool,     // Opens a nested pie instead of launching
+    is_separator: bool,   // Empty gap: not drawn or selectable
+}
+
+/// Angle between two directions (radians), going the short way round
+fn circular_gap(a: f32, b: f32) -> f32 {
+    let d = (a - b).rem_euclid(2.0 * PI);
+    d.min(2.0 * PI - d)
+}
+
+/// Lay out a ring: its items in clockwise order from the top, with the
+/// (start, end) angle of each slice
+///
+/// Without pins every slice is equally wide. A pinned item (angle by item ID)
+/// is centered on its direction, as wide as an equal share or narrower when
+/// another pin is close. The other items fill the gaps between pins in list
+/// order, spread over the gaps in proportion to their size; a gap left empty
+/// is split between the pins beside it. Of two pins less than half a slice
+/// apart, the first in the list wins and the other item is laid out as unpinned.
+fn layout_ring(apps: Vec<AppInfo>, pins: &BTreeMap<String, f32>) -> (Vec<AppInfo>, Vec<(f32, f32)>) {
+    let n = apps.len();
+    let width = 2.0 * PI / n.max(1) as f32;
+    // Positions are measured clockwise from the top, in [0, 2π)
+    let from_top = |angle: f32| (angle + PI / 2.0).rem_euclid(2.0 * PI);
+
+    let mut pinned: Vec<(usize, f32)> = Vec::new();
+    for (i, app) in apps.iter().enumerate() {
+        let Some(&angle) = pins.get(&app.id) else { continue };
+        let position = from_top(angle);
+        if pinned.iter().all(|&(_, other)| circular_gap(position, other) >= width / 2.0) {
+            pinned.push((i, position));
+        }
+    }
+    if pinned.is_empty() {
+        let spans = (0..n)
+            .map(|i| {
+                // Start from top (-PI/2), go clockwise
+                let angle = -PI / 2.0 + i as f32 * width;
+                (angle - width / 2.0, angle + width / 2.0)
+            })
+            .collect();
+        return (apps, spans);
+    }
+    pinned.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    // Angle from each pin to the next one clockwise
+    let k = pinned.len();
+    let gap_after = |j: usize| {
+        if k == 1 {
+            2.0 * PI
+        } else {
+            (pinned[(j + 1) % k].1 - pinned[j].1).rem_euclid(2.0 * PI)
+        }
+    };
+    let half: Vec<f32> = (0..k)
+        .map(|j| (width / 2.0).min(gap_after(j) / 2.0).min(gap_after((j + k - 1) % k) / 2.0))
+        .collect();
+    let mut pinned_spans: Vec<(f32, f32)> = (0..k).map(|j| (pinned[j].1 - half[j], pinned[j].1 + half[j])).collect();
+    let free: Vec<f32> = (0..k)
+        .map(|j| (gap_after(j) - half[j] - half[(j + 1) % k]).max(0.0))
+        .collect();
+
+    // Share the unpinned items between the gaps by largest remainder
+    let unpinned_count = n - k;
+    let total_free: f32 = free.iter().sum();
+    let mut counts = vec![0; k];
+    if unpinned_count > 0 && total_free > 0.0 {
+        let quotas: Vec<f32> = free.iter().map(|f| f / total_free * unpinned_count as f32).collect();
+        for (count, quota) in counts.iter_mut().zip(&quotas) {
+            *count = quota.floor() as usize;
+        }
+        let mut by_remainder: Vec<usize> = (0..k).collect();
+        by_remainder.sort_by(|&a, &b| (quotas[b] - quotas[b].floor()).total_cmp(&(quotas[a] - quotas[a].floor())));
+        let assigned: usize = counts.iter().sum();
+        for &j in by_remainder.iter().take(unpinned_count - assigned) {
+            counts[j] += 1;
+        }
+    }
+
+    let mut slots: Vec<(f32, f32)> = Vec::new();
+    for j in 0..k {
+        let start = pinned_spans[j].1;
+        if counts[j] == 0 {
+            pinned_spans[j].1 += free[j] / 2.0;
+            pinned_spans[(j + 1) % k].0 -= free[j] / 2.0;
+        } else {
+            let slot = free[j] / counts[j] as f32;
+            slots.extend((0..counts[j]).map(|s| (start + s as f32 * slot, start + (s + 1) as f32 * slot)));
+        }
+    }
+    let center = |(start, end): (f32, f32)| ((start + end) / 2.0).rem_euclid(2.0 * PI);
+    slots.sort_by(|&a, &b| center(a).total_cmp(&center(b)));
+
+    let is_pinned = |i: usize| pinned.iter().any(|&(p, _)| p == i);
+    let unpinned = (0..n).filter(|&i| !is_pinned(i));
+    let mut placed: Vec<(usize, (f32, f32))> = pinned.iter().map(|&(i, _)| i).zip(pinned_spans).collect();
+    placed.extend(unpinned.zip(slots));
+    placed.sort_by(|&(_, a), &(_, b)| center(a).total_cmp(&center(b)));
+
+    let mut items: Vec<Option<AppInfo>> = apps.into_iter().map(Some).collect();
+    placed
+        .into_iter()
+        .filter_map(|(i, (start, end))| Some((items[i].take()?, (start - PI / 2.0, end - PI / 2.0))))
+        .unzip()
+}
+
+/// Build slice data for a ring laid out by [`layout_ring`] (positions calculated during draw)
+fn build_slices(apps: &[AppInfo], spans: &[(f32, f32)], icon_size: u16, icon_only_highlight: bool) -> Vec<AppSlice> {
+    // Get glow color from theme for pre-creating tinted icon handles
+    let glow_color = icon_only_highlight.then(|| PieTheme::current().segment_hover_color);
+
+    apps
+        .iter()
+        .zip(spans)
+        .enumerate()
+        .map(|(i, (app, &(start_angle, end_angle)))| {
+            let angle = (start_angle + end_angle) / 2.0;
+
+            let icon_path = app.icon.as_ref()
+                .and_then(|name| cached_icon_path(name, icon_size));
+
+            // Pre-create tinted glow handle if icon_only_highlight is enabled
+            let glow_handle = glow_color
+                .and_then(|color| create_glow_handle(icon_path.as_ref()?, &color, icon_size));
+
+            AppSlice {
+                index: i,
+                name: app.name.clone(),
+                icon_path,
+                angle,
+                start_angle,
+                end_angle,
+                running_count: app.running_count,
+                glow_handle,
+                is_submenu: app.is_submenu(),
+                is_separator: app.is_separator(),
+            }
+        })
+        .collect()
+}
+
+/// A parent ring saved while one of its submenus is open
+struct MenuLevel {
+    /// Items of the parent ring
+    apps: Vec<AppInfo>,
 }
 
 /// State for the pie menu application
@@ -308,13 +802,17 @@ struct PieMenuApp {
     apps: Vec<AppInfo>,
     slices: Vec<AppSlice>,
     hovered_slice: Option<usize>,
+    /// The hovered slice was picked by a touchpad drag, not the pointer
+    touch_pointing: bool,
     tick_count: u32,  // Count ticks to trigger redraws on scaled displays
     /// Position mode: None = centered window, Some = full-screen with menu at position
     cursor_position: Option<(f32, f32)>,
-    /// Dynamic menu radius based on number of apps
-    menu_radius: f32,
-    /// Dynamic inner radius (scales with menu size)
-    inner_radius: f32,
+    /// Concentric rings of slices, innermost first (sized by their item counts)
+    rings: Vec<Ring>,
+    /// Most rings the items are split into
+    max_rings: usize,
+    /// Items on a ring before the rest overflow outward
+    ring_capacity: usize,
     /// Theme: show background behind pie slices (also controls indicator ring background)
     show_background: bool,
     /// Theme: highlight only icon on hover (vs whole segment)
@@ -327,15 +825,57 @@ struct PieMenuApp {
     hover_offset: f32,
     /// Configurable animation speed
     animation_speed: f32,
+    /// Configurable spacing between icons (used when resizing for submenus)
+    icon_spacing: f32,
+    /// Directions (radians) of pinned items, by item ID
+    pins: BTreeMap<String, f32>,
+    /// Fan the slices over part of the circle near screen edges
+    fan_at_edges: bool,
+    /// Size of the pie's surface, once known
+    screen: Option<(f32, f32)>,
+    /// Where the pie sits on its surface, once the surface size is known
+    placement: Option<Placement>,
+    /// How long a slice must be hovered to trigger `dwell_action` (None = off)
+    dwell_time: Option<Duration>,
+    /// What a completed dwell does
+    dwell_action: DwellAction,
+    /// Slice the dwell timer is running for, with its start (None once it fired)
+    dwell: Option<(usize, Option<Instant>)>,
+    /// Dwell progress on the hovered slice (0.0 to 1.0), drawn as a fill
+    dwell_progress: f32,
+    /// Parent rings of the currently open submenu (empty at the top level)
+    menu_stack: Vec<MenuLevel>,
+    /// What to do on close
+    host: Host,
+    /// The pie's layer surface
+    surface: Id,
+    /// Set once the pie has closed (service host only)
+    closed: bool,
+    /// Dark theme, read once when the pie opens
+    dark_mode: bool,
+    /// Search typed so far (empty when not searching)
+    query: String,
+    /// The ring's items before the search filtered them
+    unfiltered: Option<Vec<AppInfo>>,
+    /// All installed apps, loaded the first time a search needs them
+    installed: Option<Vec<AppInfo>>,
 }
 
 impl PieMenuApp {
-    fn new_at(apps: Vec<AppInfo>, position: Option<(f32, f32)>) -> (Self, Task<Message>) {
-        // Load config for all settings
-        let config = PieMenuConfig::load();
+    fn new_at(apps: Vec<AppInfo>, position: Option<(f32, f32)>, host: Host) -> (Self, Task<Message>) {
+        Self::with_config(apps, position, host, &PieMenuConfig::load(), is_dark_mode())
+    }
+
+    /// Open a pie with the given settings and theme mode (`new_at` reads both
+    /// from the system)
+    fn with_config(
+        apps: Vec<AppInfo>,
+        position: Option<(f32, f32)>,
+        host: Host,
+        config: &PieMenuConfig,
+        dark_mode: bool,
+    ) -> (Self, Task<Message>) {
         let icon_size = config.icon_size;
-        let menu_radius = calculate_menu_radius(apps.len(), config.icon_spacing);
-        let inner_radius = calculate_inner_radius(menu_radius);
 
         let mut settings = SctkLayerSurfaceSettings::default();
         settings.keyboard_interactivity = KeyboardInteractivity::OnDemand;
@@ -346,66 +886,331 @@ impl PieMenuApp {
         settings.size = Some((None, None)); // Fill available space
         settings.exclusive_zone = -1;
 
-        // Pre-calculate slice data (positions calculated during draw)
-        let num_apps = apps.len();
-
-        // Get glow color from theme for pre-creating tinted icon handles
-        let pie_theme = PieTheme::current();
-        let glow_color = pie_theme.segment_hover_color;
-
-        let slices: Vec<AppSlice> = apps
+        let pins: BTreeMap<String, f32> = config
+            .pinned_directions
             .iter()
-            .enumerate()
-            .map(|(i, app)| {
-                let slice_angle = 2.0 * PI / num_apps as f32;
-                // Start from top (-PI/2), go clockwise
-                let angle = -PI / 2.0 + (i as f32 * slice_angle);
-                let start_angle = angle - slice_angle / 2.0;
-                let end_angle = angle + slice_angle / 2.0;
-
-                let icon_path = app.icon.as_ref()
-                    .and_then(|name| find_icon_path(name, icon_size));
-
-                // Pre-create tinted glow handle if icon_only_highlight is enabled
-                let glow_handle = if config.icon_only_highlight {
-                    icon_path.as_ref().and_then(|p| create_glow_handle(p, &glow_color, icon_size))
-                } else {
-                    None
-                };
-
-                AppSlice {
-                    index: i,
-                    name: app.name.clone(),
-                    icon_path,
-                    angle,
-                    start_angle,
-                    end_angle,
-                    running_count: app.running_count,
-                    glow_handle,
-                }
-            })
+            .filter_map(|(id, direction)| Some((id.clone(), config::pin_angle(direction)?)))
             .collect();
 
-        let num_slices = slices.len();
-        let app = Self {
-            apps,
-            slices,
+        let mut app = Self {
+            apps: Vec::new(),
+            slices: Vec::new(),
             hovered_slice: None,
+            touch_pointing: false,
             tick_count: 0,
             cursor_position: position,
-            menu_radius,
-            inner_radius,
+            rings: Vec::new(),
+            max_rings: config.max_rings,
+            ring_capacity: config.ring_capacity,
             show_background: config.show_background,
             icon_only_highlight: config.icon_only_highlight,
-            hover_offsets: vec![0.0; num_slices],
+            hover_offsets: Vec::new(),
             icon_size,
             hover_offset: config.hover_offset,
             animation_speed: config.animation_speed,
+            icon_spacing: config.icon_spacing,
+            pins,
+            fan_at_edges: config.fan_at_edges,
+            screen: None,
+            placement: None,
+            dwell_time: (config.dwell_ms > 0).then(|| Duration::from_millis(config.dwell_ms)),
+            dwell_action: config.dwell_action,
+            dwell: None,
+            dwell_progress: 0.0,
+            menu_stack: Vec::new(),
+            host,
+            surface: settings.id,
+            closed: false,
+            dark_mode,
+            query: String::new(),
+            unfiltered: None,
+            installed: None,
         };
+        // Pre-calculate slice data (positions calculated during draw)
+        app.set_items(apps);
 
         (app, get_layer_surface(settings))
     }
 
+    /// Close the pie: exit when it has its own process, else remove its surface
+    fn close(&mut self) -> Task<Message> {
+        if self.host == Host::Process {
+            std::process::exit(0);
+        }
+        self.closed = true;
+        destroy_layer_surface(self.surface)
+    }
+
+    /// Replace the items in the ring, recalculating slices and ring sizes
+    ///
+    /// Items are split into concentric rings, each laid out on its own, and
+    /// stored ring by ring so every ring is a run of consecutive indices.
+    fn set_items(&mut self, apps: Vec<AppInfo>) {
+        let mut laid_out = Vec::new();
+        let mut spans = Vec::new();
+        let mut counts = Vec::new();
+        for ring in split_rings(apps, self.max_rings, self.ring_capacity) {
+            let (ring, ring_spans) = layout_ring(ring, &self.pins);
+            counts.push(ring.len());
+            laid_out.extend(ring);
+            spans.extend(ring_spans);
+        }
+        let apps = laid_out;
+        self.slices = build_slices(&apps, &spans, self.icon_size, self.icon_only_highlight);
+        self.rings = build_rings(&counts, self.icon_size, self.icon_spacing, 2.0 * PI);
+        self.hover_offsets = vec![0.0; self.slices.len()];
+        self.hovered_slice = None;
+        self.touch_pointing = false;
+        self.dwell = None;
+        self.dwell_progress = 0.0;
+        self.query.clear();
+        self.unfiltered = None;
+        self.apps = apps;
+        self.place();
+    }
+
+    /// Place the pie on its surface again, after its rings or the surface changed
+    fn place(&mut self) {
+        self.placement = self.screen.map(|screen| {
+            Placement::new(&self.rings, self.cursor_position, screen, self.icon_size, self.icon_spacing, self.fan_at_edges)
+        });
+    }
+
+    /// The arc the pie is fanned over, as the canvas places it (None for a full circle)
+    fn fan(&self) -> Option<Fan> {
+        self.placement.as_ref()?.fan
+    }
+
+    /// The slice of the inner ring in a direction (radians, screen
+    /// coordinates); picks by direction alone choose from the inner ring
+    fn slice_in_direction(&self, angle: f32) -> Option<usize> {
+        let slices = &self.slices[self.rings.first()?.items.clone()];
+        let angle = match self.fan() {
+            Some(fan) => fan.to_layout(slices.first()?.start_angle, angle)?,
+            None => angle,
+        };
+        slice_at_angle(slices, angle).map(|slice| slice.index)
+    }
+
+    /// Hover a slice with the pointer or a touchpad drag
+    ///
+    /// Starts the dwell timer when the slice changes. Keyboard and search
+    /// highlights set `hovered_slice` directly, so they never dwell.
+    fn hover(&mut self, slice: Option<usize>) {
+        if self.hovered_slice != slice {
+            self.hovered_slice = slice;
+            self.dwell = slice
+                .filter(|_| self.dwell_time.is_some())
+                .map(|index| (index, Some(Instant::now())));
+        }
+    }
+
+    /// Highlight the next selectable slice clockwise (or anticlockwise)
+    fn move_selection(&mut self, clockwise: bool) {
+        if let Some(index) = step_selection(&self.apps, self.hovered_slice, clockwise) {
+            self.hovered_slice = Some(index);
+        }
+    }
+
+    /// Filter the ring to the items matching `query`, or restore it if empty
+    ///
+    /// When nothing in the ring (or its submenus) matches, searches all
+    /// installed apps instead. The top match is highlighted, so Enter
+    /// launches it.
+    fn set_query(&mut self, query: String) {
+        let all = self.unfiltered.take().unwrap_or_else(|| std::mem::take(&mut self.apps));
+        if query.trim().is_empty() {
+            self.set_items(all);
+            return;
+        }
+
+        let mut found = search_items(&all, &query);
+        if found.is_empty() {
+            let installed = self.installed.get_or_insert_with(apps::load_installed_apps);
+            found = search_items(installed, &query);
+        }
+        self.set_items(found);
+        self.hovered_slice = step_selection(&self.apps, None, true);
+        self.unfiltered = Some(all);
+        self.query = query;
+    }
+
+    /// Show `items` as a nested ring, remembering the current one for Back
+    ///
+    /// Going back from a search result returns to the ring before the search.
+    fn enter_level(&mut self, items: Vec<AppInfo>) {
+        let parent = self.unfiltered.take().unwrap_or_else(|| std::mem::take(&mut self.apps));
+        self.menu_stack.push(MenuLevel { apps: parent });
+        self.set_items(items);
+    }
+
+    /// Query the open windows of the item at `index` off the UI thread
+    ///
+    /// Both lookups run subprocesses, so they go through a task instead of
+    /// blocking the pie. Workspace names are only fetched for a context ring
+    /// that targets a single window.
+    fn query_windows(&self, index: usize, query: WindowQuery) -> Task<Message> {
+        let Some(item) = self.apps.get(index) else {
+            return Task::none();
+        };
+        let id = item.id.clone();
+        let app_id = item.id.clone();
+        let running = matches!(item.kind, ItemKind::App) && item.running_count > 0;
+        let is_window = matches!(item.kind, ItemKind::Window(_));
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let app_windows: Vec<_> = if running {
+                        windows::query_windows_via_subprocess()
+                            .into_iter()
+                            .filter(|w| apps::app_id_matches(&app_id, &w.app_id))
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    let workspaces = if query == WindowQuery::Context && (is_window || app_windows.len() == 1) {
+                        windows::query_workspaces_via_subprocess()
+                    } else {
+                        Vec::new()
+                    };
+                    (app_windows, workspaces)
+                })
+                .await
+                .unwrap_or_default()
+            },
+            move |(windows, workspaces)| Message::WindowsLoaded {
+                index,
+                id: id.clone(),
+                query,
+                windows,
+                workspaces,
+            },
+        )
+    }
+
+    /// Switch to a running app's window, or choose between several
+    fn switch_to(&mut self, index: usize, mut app_windows: Vec<windows::WindowInfo>) -> Task<Message> {
+        let app = &self.apps[index];
+        // Several windows: let the user pick one from a ring of titles
+        if app_windows.len() > 1 {
+            println!("Choosing between {} windows of {}", app_windows.len(), app.name);
+            let items = app_windows.into_iter().map(|w| AppInfo::window(app, w)).collect();
+            self.enter_level(items);
+            return Task::none();
+        }
+        // Switch to existing window. Activate the window that matched,
+        // since its app_id can differ from the desktop ID (PWAs, Flatpaks)
+        println!("Switching to: {}", app.name);
+        let result = match app_windows.pop() {
+            Some(window) => windows::activate_window(&window),
+            None => windows::activate_window_by_app_id(&app.id),
+        };
+        match result {
+            Ok(true) => {
+                dbus::emit(dbus::Event::ItemActivated { id: app.id.clone(), name: app.name.clone() });
+                self.close()
+            }
+            Ok(false) => {
+                eprintln!("No window found for {}, launching new instance", app.id);
+                // Fall through to launch new instance
+                self.update(Message::LaunchApp(index))
+            }
+            Err(e) => {
+                eprintln!("Failed to activate: {}", e);
+                Task::none()
+            }
+        }
+    }
+
+    /// Open the context ring of the item at `index`, or launch it if there
+    /// is nothing to offer
+    fn show_context(
+        &mut self,
+        index: usize,
+        app_windows: Vec<windows::WindowInfo>,
+        workspaces: Vec<String>,
+    ) -> Task<Message> {
+        match self.context_items(index, app_windows, workspaces) {
+            Some(items) => {
+                println!("Actions for: {}", self.apps[index].name);
+                self.enter_level(items);
+                Task::none()
+            }
+            None => self.update(Message::LaunchApp(index)),
+        }
+    }
+
+    /// Items for the right-click context ring of an app or window
+    ///
+    /// Offers a new instance and the app's desktop actions, plus window
+    /// management actions when a single window is targeted. An app with
+    /// several windows gets a "Windows" sub-ring instead, where each window
+    /// can be right-clicked in turn. `app_windows` and `workspaces` come from
+    /// [`Self::query_windows`]. Returns `None` if there is nothing to offer
+    /// beyond a plain launch.
+    fn context_items(
+        &self,
+        index: usize,
+        mut app_windows: Vec<windows::WindowInfo>,
+        workspaces: Vec<String>,
+    ) -> Option<Vec<AppInfo>> {
+        let item = self.apps.get(index)?;
+        let (window, other_windows) = match &item.kind {
+            ItemKind::Window(window) => (Some(window.clone()), Vec::new()),
+            ItemKind::App => {
+                if app_windows.len() == 1 {
+                    (app_windows.pop(), Vec::new())
+                } else {
+                    (None, app_windows)
+                }
+            }
+            _ => return None,
+        };
+        if window.is_none() && other_windows.is_empty() && item.actions.is_empty() {
+            return None;
+        }
+
+        let mut items = Vec::new();
+        if item.exec.is_some() {
+            let mut new_window = item.clone();
+            new_window.name = String::from("New Window");
+            new_window.icon = Some(String::from("window-new"));
+            new_window.running_count = 0;
+            new_window.kind = ItemKind::App;
+            items.push(new_window);
+        }
+        items.extend(item.actions.iter().map(|action| AppInfo::desktop_action(item, action)));
+
+        if !other_windows.is_empty() {
+            let window_items = other_windows
+                .into_iter()
+                .map(|w| AppInfo::window(item, w))
+                .collect();
+            items.push(AppInfo::submenu("Windows", Some(String::from("focus-windows-symbolic")), window_items));
+        }
+
+        let Some(window) = window else {
+            return Some(items);
+        };
+        items.push(AppInfo::window_action(&window, WindowAction::Close, "Close", "window-close"));
+        items.push(AppInfo::window_action(&window, WindowAction::Minimize, "Minimize", "window-minimize"));
+        items.push(AppInfo::window_action(&window, WindowAction::ToggleMaximize, "Maximize", "window-maximize"));
+        items.push(AppInfo::window_action(&window, WindowAction::ToggleFullscreen, "Fullscreen", "view-fullscreen"));
+
+        if workspaces.len() > 1 {
+            let targets = workspaces
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    AppInfo::window_action(&window, WindowAction::MoveToWorkspace(i), name, "view-paged-symbolic")
+                })
+                .collect();
+            items.push(AppInfo::submenu("Move to Workspace", Some(String::from("view-paged-symbolic")), targets));
+        }
+
+        Some(items)
+    }
+
     fn title(&self, _id: Id) -> String {
         String::from("Pie Menu")
     }
@@ -414,53 +1219,116 @@ impl PieMenuApp {
         match message {
             Message::LaunchApp(index) => {
                 if let Some(app) = self.apps.get(index) {
-                    if let Some(ref exec) = app.exec {
-                        println!("Launching: {} ({})", app.name, exec);
-                        // Parse exec command, removing field codes like %u, %F, etc.
-                        let exec_clean: String = exec
-                            .split_whitespace()
-                            .filter(|s| !s.starts_with('%'))
-                            .collect::<Vec<_>>()
-                            .join(" ");
+                    if !app.is_submenu() && !app.is_separator() {
+                        dbus::emit(dbus::Event::ItemActivated { id: app.id.clone(), name: app.name.clone() });
+                    }
+                    // Count launches for smart ordering (switching windows isn't a launch)
+                    if matches!(app.kind, ItemKind::App | ItemKind::Command | ItemKind::Open(_) | ItemKind::Builtin(_)) {
+                        history::record_launch(&app.id);
+                    }
+                    match &app.kind {
+                        ItemKind::Submenu(_) => return self.update(Message::OpenSubmenu(index)),
+                        ItemKind::Separator => return Task::none(),
+                        ItemKind::Command => {
+                            if let Some(ref exec) = app.exec {
+                                println!("Running: {} ({})", app.name, exec);
+                                let _ = spawn_detached(
+                                    Command::new("sh").arg("-c").arg(format!("sleep 0.1 && {}", exec)),
+                                );
+                            }
+                            return self.close();
+                        }
+                        ItemKind::Open(target) => {
+                            println!("Opening: {}", target);
+                            if let Err(e) = spawn_detached(Command::new("xdg-open").arg(target)) {
+                                eprintln!("Failed to open {}: {}", target, e);
+                            }
+                            return self.close();
+                        }
+                        ItemKind::Builtin(action) => {
+                            println!("Builtin action: {:?}", action);
+                            if let Some(cmd) = action.command() {
+                                // Same delay as app launches so the pie isn't in the screenshot
+                                let _ = spawn_detached(
+                                    Command::new("sh").arg("-c").arg(format!("sleep 0.1 && {}", cmd)),
+                                );
+                            }
+                            return self.close();
+                        }
+                        ItemKind::Window(window) => {
+                            println!("Switching to window: {} ({})", window.title, window.app_id);
+                            match windows::activate_window(window) {
+                                Ok(true) => {}
+                                Ok(false) => eprintln!("Window '{}' is no longer open", window.title),
+                                Err(e) => eprintln!("Failed to activate: {}", e),
+                            }
+                            return self.close();
+                        }
+                        ItemKind::WindowAction(window, action) => {
+                            println!("{:?}: {} ({})", action, window.title, window.app_id);
+                            match windows::perform_window_action(window, *action) {
+                                Ok(true) => {}
+                                Ok(false) => eprintln!("Window '{}' is no longer open", window.title),
+                                Err(e) => eprintln!("Failed to {:?}: {}", action, e),
+                            }
+                            return self.close();
+                        }
+                        ItemKind::App => {}
+                    }
+                    if let Some(args) = app.launch_args() {
+                        println!("Launching: {} ({})", app.name, args.join(" "));
 
                         // Launch via shell with a small delay so the pie menu window
                         // closes before the app starts. This prevents apps like
                         // cosmic-screenshot from capturing the menu in their screenshot.
-                        let _ = Command::new("sh")
-                            .arg("-c")
-                            .arg(format!("sleep 0.1 && {}", exec_clean))
-                            .spawn();
+                        // The arguments are passed through "$@" so they aren't re-split.
+                        let mut command = Command::new("sh");
+                        command.arg("-c").arg("sleep 0.1 && exec \"$@\"").arg("sh").args(&args);
+                        if let Some(dir) = app.working_dir.as_ref().filter(|dir| dir.is_dir()) {
+                            command.current_dir(dir);
+                        }
+                        let _ = spawn_detached(&mut command);
                     }
                 }
-                std::process::exit(0);
-            }
-            Message::Close => {
-                std::process::exit(0);
+                self.close()
             }
+            Message::Close => self.close(),
             Message::CanvasEvent(PieCanvasMessage::HoverSegment(segment)) => {
-                if self.hovered_slice != segment {
-                    self.hovered_slice = segment;
+                // A one-finger drag nudges the pointer around the center too;
+                // don't let that drop the slice the drag is pointing at
+                if segment.is_none() && self.touch_pointing {
+                    return Task::none();
                 }
+                self.touch_pointing = false;
+                self.hover(segment);
                 Task::none()
             }
+            Message::OpenSubmenu(index) => {
+                let children = match self.apps.get(index).map(|app| &app.kind) {
+                    Some(ItemKind::Submenu(children)) => children.clone(),
+                    _ => return Task::none(),
+                };
+                println!("Opening submenu: {}", self.apps[index].name);
+                self.enter_level(children);
+                Task::none()
+            }
+            Message::Back => {
+                match self.menu_stack.pop() {
+                    Some(parent) => {
+                        self.set_items(parent.apps);
+                        Task::none()
+                    }
+                    None => self.update(Message::Close),
+                }
+            }
             Message::CanvasEvent(PieCanvasMessage::ClickSegment(index)) => {
                 if let Some(app) = self.apps.get(index) {
-                    if app.running_count > 0 {
-                        // Switch to existing window
-                        println!("Switching to: {}", app.name);
-                        match windows::activate_window_by_app_id(&app.id) {
-                            Ok(true) => {
-                                std::process::exit(0);
-                            }
-                            Ok(false) => {
-                                eprintln!("No window found for {}, launching new instance", app.id);
-                                // Fall through to launch new instance
-                                return self.update(Message::LaunchApp(index));
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to activate: {}", e);
-                            }
-                        }
+                    if app.is_submenu() {
+                        return self.update(Message::OpenSubmenu(index));
+                    } else if !matches!(app.kind, ItemKind::App) {
+                        return self.update(Message::LaunchApp(index));
+                    } else if app.running_count > 0 {
+                        return self.query_windows(index, WindowQuery::Switch);
                     } else {
                         // Non-running app: launch it
                         return self.update(Message::LaunchApp(index));
@@ -469,14 +1337,104 @@ impl PieMenuApp {
                 Task::none()
             }
             Message::CanvasEvent(PieCanvasMessage::RightClickSegment(index)) => {
-                self.update(Message::LaunchApp(index))
+                if self.apps.get(index).is_some_and(|app| app.is_submenu()) {
+                    return self.update(Message::OpenSubmenu(index));
+                }
+                // Apps with desktop actions, running apps and windows get a context ring
+                let has_windows = self.apps.get(index).is_some_and(|app| match app.kind {
+                    ItemKind::App => app.running_count > 0,
+                    ItemKind::Window(_) => true,
+                    _ => false,
+                });
+                if has_windows {
+                    return self.query_windows(index, WindowQuery::Context);
+                }
+                self.show_context(index, Vec::new(), Vec::new())
+            }
+            Message::WindowsLoaded { index, id, query, windows, workspaces } => {
+                // The ring may have changed while the windows were queried
+                if self.apps.get(index).map(|app| &app.id) != Some(&id) {
+                    return Task::none();
+                }
+                match query {
+                    WindowQuery::Switch => self.switch_to(index, windows),
+                    WindowQuery::Context => self.show_context(index, windows, workspaces),
+                }
+            }
+            Message::Release(angle) => {
+                // Pick the slice under the pointer, else the one in the direction
+                // the trigger moved; a release without either leaves the pie open
+                let index = self.hovered_slice.or_else(|| angle.and_then(|angle| self.slice_in_direction(angle)));
+                match index {
+                    Some(index) => self.update(Message::CanvasEvent(PieCanvasMessage::ClickSegment(index))),
+                    None => Task::none(),
+                }
+            }
+            Message::Point(angle) => {
+                if let Some(index) = self.slice_in_direction(angle) {
+                    self.hover(Some(index));
+                    self.touch_pointing = true;
+                }
+                Task::none()
+            }
+            Message::Resized(width, height) => {
+                self.screen = Some((width, height));
+                self.place();
+                Task::none()
             }
             Message::CanvasEvent(PieCanvasMessage::ClickCenter) => {
-                self.update(Message::Close)
+                // Center goes back one level in a submenu, closes at the top level
+                self.update(Message::Back)
             }
-            Message::KeyPressed(key) => {
-                if matches!(key, Key::Named(keyboard::key::Named::Escape)) {
-                    std::process::exit(0);
+            Message::KeyPressed(key, modifiers) => {
+                match key {
+                    Key::Named(keyboard::key::Named::Escape) => {
+                        if !self.query.is_empty() {
+                            self.set_query(String::new());
+                        } else if self.menu_stack.is_empty() {
+                            return self.close();
+                        } else {
+                            return self.update(Message::Back);
+                        }
+                    }
+                    Key::Named(keyboard::key::Named::Backspace) => {
+                        if self.query.is_empty() {
+                            return self.update(Message::Back);
+                        }
+                        let mut query = self.query.clone();
+                        query.pop();
+                        self.set_query(query);
+                    }
+                    Key::Named(keyboard::key::Named::Tab) => self.move_selection(!modifiers.shift()),
+                    Key::Named(keyboard::key::Named::ArrowRight | keyboard::key::Named::ArrowDown) => {
+                        self.move_selection(true);
+                    }
+                    Key::Named(keyboard::key::Named::ArrowLeft | keyboard::key::Named::ArrowUp) => {
+                        self.move_selection(false);
+                    }
+                    Key::Named(keyboard::key::Named::Enter) => {
+                        // Same as clicking the highlighted slice, or the top search match
+                        let top_match = || nth_selectable(&self.apps, 0).filter(|_| !self.query.is_empty());
+                        if let Some(index) = self.hovered_slice.or_else(top_match) {
+                            return self.update(Message::CanvasEvent(PieCanvasMessage::ClickSegment(index)));
+                        }
+                    }
+                    Key::Named(keyboard::key::Named::Space) if !self.query.is_empty() => {
+                        self.set_query(format!("{} ", self.query));
+                    }
+                    Key::Character(text) if !(modifiers.control() || modifiers.alt() || modifiers.logo()) => {
+                        // 1-9 pick a slice directly, unless a search is being typed;
+                        // other keys search
+                        match text.parse::<usize>() {
+                            Ok(digit @ 1..=9) if self.query.is_empty() => {
+                                if let Some(index) = nth_selectable(&self.apps, digit - 1) {
+                                    return self.update(Message::CanvasEvent(PieCanvasMessage::ClickSegment(index)));
+                                }
+                            }
+                            _ => self.set_query(format!("{}{}", self.query, text.as_str())),
+                        }
+                    }
+                    _ => {}
                 }
                 Task::none()
             }
@@ -486,18 +1444,12 @@ impl PieMenuApp {
 
                 // Animate hover offsets for smooth icon movement (rubber band effect)
                 if self.icon_only_highlight {
-                    let n = self.hover_offsets.len();
                     for (i, offset) in self.hover_offsets.iter_mut().enumerate() {
-                        let target = if let Some(hovered) = self.hovered_slice {
-                            let dist = circular_distance(i, hovered, n);
-                            if dist < RUBBER_BAND_RADIAL.len() {
-                                RUBBER_BAND_RADIAL[dist]
-                            } else {
-                                0.0
-                            }
-                        } else {
-                            0.0
-                        };
+                        // Only neighbors on the hovered slice's own ring are pulled
+                        let target = self.hovered_slice
+                            .and_then(|hovered| ring_distance(&self.rings, i, hovered))
+                            .and_then(|dist| RUBBER_BAND_RADIAL.get(dist).copied())
+                            .unwrap_or(0.0);
                         if (*offset - target).abs() > 0.01 {
                             *offset += (target - *offset) * self.animation_speed;
                         } else {
@@ -506,6 +1458,27 @@ impl PieMenuApp {
                     }
                 }
 
+                // Dwell: resting the pointer on a slice fills it, then acts on it
+                // like a click. The keyboard moving the highlight away stops it.
+                if let Some(dwell_time) = self.dwell_time {
+                    if self.dwell.is_some_and(|(index, _)| Some(index) != self.hovered_slice) {
+                        self.dwell = None;
+                    }
+                    self.dwell_progress = 0.0;
+                    if let Some((index, Some(since))) = self.dwell {
+                        let progress = since.elapsed().as_secs_f32() / dwell_time.as_secs_f32();
+                        if progress >= 1.0 {
+                            self.dwell = Some((index, None));
+                            let event = match self.dwell_action {
+                                DwellAction::Activate => PieCanvasMessage::ClickSegment(index),
+                                DwellAction::Actions => PieCanvasMessage::RightClickSegment(index),
+                            };
+                            return self.update(Message::CanvasEvent(event));
+                        }
+                        self.dwell_progress = progress;
+                    }
+                }
+
                 Task::none()
             }
         }
@@ -513,17 +1486,23 @@ impl PieMenuApp {
 
     fn subscription(&self) -> Subscription<Message> {
         let keyboard_sub = cosmic::iced::event::listen_with(|event, _status, _window| {
-            if let Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) = event {
-                Some(Message::KeyPressed(key))
-            } else {
-                None
+            match event {
+                Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                    Some(Message::KeyPressed(key, modifiers))
+                }
+                // The surface size tells directional picks whether the pie is fanned
+                Event::Window(window::Event::Resized(size)) => Some(Message::Resized(size.width, size.height)),
+                _ => None,
             }
         });
 
         // Keep ticking for animations and initial layout
         // - First 500ms for scaled display layout
         // - Continuously when icon_only_highlight for smooth hover animations
-        let needs_ticks = self.tick_count < 10 || self.icon_only_highlight;
+        // - While the dwell timer runs on a hovered slice
+        let needs_ticks = self.tick_count < 10
+            || self.icon_only_highlight
+            || self.dwell.is_some();
         if needs_ticks {
             let tick_sub = time::every(Duration::from_millis(16)).map(|_| Message::Tick); // ~60fps
             Subscription::batch([keyboard_sub, tick_sub])
@@ -534,23 +1513,34 @@ impl PieMenuApp {
 
     fn view(&self, _id: Id) -> Element<'_, Message> {
         // Get hovered app name for center display
+        // Inside a submenu, an empty center shows that clicking it goes back
         let hovered_name = self.hovered_slice
             .and_then(|i| self.slices.get(i))
             .map(|s| s.name.clone())
-            .unwrap_or_default();
+            .unwrap_or_else(|| {
+                if self.menu_stack.is_empty() {
+                    String::new()
+                } else {
+                    String::from("Back")
+                }
+            });
 
         let pie_canvas = canvas(PieCanvas {
             slices: &self.slices,
             hovered: self.hovered_slice,
             cursor_position: self.cursor_position,
-            menu_radius: self.menu_radius,
-            inner_radius: self.inner_radius,
+            rings: &self.rings,
+            placement: self.placement.as_ref(),
             hovered_name,
+            query: &self.query,
             show_background: self.show_background,
             icon_only_highlight: self.icon_only_highlight,
             hover_offsets: &self.hover_offsets,
             icon_size: self.icon_size,
             hover_offset: self.hover_offset,
+            icon_spacing: self.icon_spacing,
+            fan_at_edges: self.fan_at_edges,
+            dwell_progress: self.dwell_progress,
         });
 
         // Always full-screen mode for reliable layer surface behavior
@@ -558,7 +1548,7 @@ impl PieMenuApp {
     }
 
     fn theme(&self, _id: Id) -> Theme {
-        if is_dark_mode() {
+        if self.dark_mode {
             Theme::Dark
         } else {
             Theme::Light
@@ -566,18 +1556,101 @@ impl PieMenuApp {
     }
 }
 
+/// The slice covering a direction from the center (radians, screen
+/// coordinates); separators are never selectable
+fn slice_at_angle(slices: &[AppSlice], mut angle: f32) -> Option<&AppSlice> {
+    slices.iter().filter(|slice| !slice.is_separator).find(|slice| {
+        let mut start = slice.start_angle;
+        let mut end = slice.end_angle;
+
+        // Normalize angles for comparison
+        while start > PI { start -= 2.0 * PI; }
+        while start < -PI { start += 2.0 * PI; }
+        while end > PI { end -= 2.0 * PI; }
+        while end < -PI { end += 2.0 * PI; }
+        while angle > PI { angle -= 2.0 * PI; }
+        while angle < -PI { angle += 2.0 * PI; }
+
+        // Handle wrap-around
+        if start > end {
+            angle >= start || angle <= end
+        } else {
+            angle >= start && angle <= end
+        }
+    })
+}
+
+/// Rounded rectangle with fully rounded ends, centered on `center`
+fn pill_path(center: Point, pill_width: f32, pill_height: f32) -> Path {
+    let pill_radius = pill_height / 2.0; // Fully rounded ends
+    Path::new(|builder| {
+        // Draw rounded rectangle (pill shape)
+        let left = center.x - pill_width / 2.0;
+        let right = center.x + pill_width / 2.0;
+        let top = center.y - pill_height / 2.0;
+        let bottom = center.y + pill_height / 2.0;
+        let r = pill_radius.min(pill_width / 2.0);
+
+        // Start at top-left after the curve
+        builder.move_to(Point::new(left + r, top));
+        // Top edge
+        builder.line_to(Point::new(right - r, top));
+        // Top-right curve (approximate with lines)
+        for i in 0..=8 {
+            let angle = -PI / 2.0 + (i as f32 / 8.0) * (PI / 2.0);
+            builder.line_to(Point::new(
+                right - r + r * angle.cos(),
+                top + r + r * angle.sin(),
+            ));
+        }
+        // Right edge
+        builder.line_to(Point::new(right, bottom - r));
+        // Bottom-right curve
+        for i in 0..=8 {
+            let angle = 0.0 + (i as f32 / 8.0) * (PI / 2.0);
+            builder.line_to(Point::new(
+                right - r + r * angle.cos(),
+                bottom - r + r * angle.sin(),
+            ));
+        }
+        // Bottom edge
+        builder.line_to(Point::new(left + r, bottom));
+        // Bottom-left curve
+        for i in 0..=8 {
+            let angle = PI / 2.0 + (i as f32 / 8.0) * (PI / 2.0);
+            builder.line_to(Point::new(
+                left + r + r * angle.cos(),
+                bottom - r + r * angle.sin(),
+            ));
+        }
+        // Left edge
+        builder.line_to(Point::new(left, top + r));
+        // Top-left curve
+        for i in 0..=8 {
+            let angle = PI + (i as f32 / 8.0) * (PI / 2.0);
+            builder.line_to(Point::new(
+                left + r + r * angle.cos(),
+                top + r + r * angle.sin(),
+            ));
+        }
+        builder.close();
+    })
+}
+
 /// Canvas widget for rendering the pie menu
 struct PieCanvas<'a> {
     slices: &'a [AppSlice],
     hovered: Option<usize>,
     /// If Some, draw the menu centered at this position; if None, center in bounds
     cursor_position: Option<(f32, f32)>,
-    /// Dynamic menu radius
-    menu_radius: f32,
-    /// Dynamic inner radius (scales with menu size)
-    inner_radius: f32,
+    /// Concentric rings of slices, innermost first
+    rings: &'a [Ring],
+    /// Where the pie sits, if the app has placed it for the current surface size
+    placement: Option<&'a Placement>,
     /// Name of hovered app (to display in center)
     hovered_name: String,
+    /// Search typed so far (shown below the ring)
+    query: &'a str,
     /// Theme: show background behind pie slices (also controls indicator ring background)
     show_background: bool,
     /// Theme: highlight only icon on hover (vs whole segment)
@@ -588,6 +1661,116 @@ struct PieCanvas<'a> {
     icon_size: u16,
     /// Configurable hover offset distance
     hover_offset: f32,
+    /// Configurable spacing between icons (for sizing fanned rings)
+    icon_spacing: f32,
+    /// Fan the slices over part of the circle near screen edges, instead of
+    /// moving the pie away from the cursor
+    fan_at_edges: bool,
+    /// Dwell progress on the hovered slice (0.0 = none, 1.0 = about to fire)
+    dwell_progress: f32,
+}
+
+/// Where the pie is drawn within the canvas bounds
+#[derive(Clone)]
+struct Placement {
+    center: Point,
+    /// Arc the slices are fanned over (None for a full circle)
+    fan: Option<Fan>,
+    /// Rings sized for the fan
+    rings: Vec<Ring>,
+}
+
+impl Placement {
+    /// Outer radius of the whole pie (of its outermost ring)
+    fn menu_radius(&self) -> f32 {
+        self.rings.last().map_or(MIN_MENU_RADIUS, |ring| ring.outer_radius)
+    }
+
+    /// Radius of the center area (inside the innermost ring)
+    fn inner_radius(&self) -> f32 {
+        self.rings.first().map_or(MIN_INNER_RADIUS, |ring| ring.inner_radius)
+    }
+
+    /// Place a pie at the cursor position, or in the center of a
+    /// `width` x `height` surface
+    ///
+    /// A pie that would cross a screen edge is fanned over the part of the
+    /// circle facing into the screen, keeping its center at the cursor;
+    /// without fanning it is moved inward until it fits.
+    fn new(
+        rings: &[Ring],
+        cursor_position: Option<(f32, f32)>,
+        (width, height): (f32, f32),
+        icon_size: u16,
+        icon_spacing: f32,
+        fan_at_edges: bool,
+    ) -> Self {
+        let Some((cx, cy)) = cursor_position else {
+            return Placement {
+                center: Point::new(width / 2.0, height / 2.0),
+                fan: None,
+                rings: rings.to_vec(),
+            };
+        };
+        let counts: Vec<usize> = rings.iter().map(|ring| ring.items.len()).collect();
+        let fanned = |span: f32| build_rings(&counts, icon_size, icon_spacing, span);
+        let half_menu = |rings: &[Ring]| {
+            menu_size(rings.last().map_or(MIN_MENU_RADIUS, |ring| ring.outer_radius), icon_size) / 2.0
+        };
+
+        if fan_at_edges {
+            if let Some(fan) = fan_at(cx, cy, width, height, |span| half_menu(&fanned(span))) {
+                return Placement {
+                    center: Point::new(cx.clamp(0.0, width), cy.clamp(0.0, height)),
+                    fan: Some(fan),
+                    rings: fanned(fan.span),
+                };
+            }
+        }
+
+        // Clamp to keep menu fully visible
+        // Handle case where screen is smaller than menu
+        let half_menu = half_menu(rings);
+        let min_x = half_menu.min(width - half_menu);
+        let max_x = half_menu.max(width - half_menu);
+        let min_y = half_menu.min(height - half_menu);
+        let max_y = half_menu.max(height - half_menu);
+        Placement {
+            center: Point::new(cx.clamp(min_x, max_x), cy.clamp(min_y, max_y)),
+            fan: None,
+            rings: rings.to_vec(),
+        }
+    }
+}
+
+impl PieCanvas<'_> {
+    /// Where the pie sits, worked out from `bounds` until the app has placed it
+    fn placement(&self, bounds: Rectangle) -> Cow<'_, Placement> {
+        match self.placement {
+            Some(placement) => Cow::Borrowed(placement),
+            None => Cow::Owned(Placement::new(
+                self.rings,
+                self.cursor_position,
+                (bounds.width, bounds.height),
+                self.icon_size,
+                self.icon_spacing,
+                self.fan_at_edges,
+            )),
+        }
+    }
+
+    /// Start, end and center angle of a slice on screen
+    fn slice_angles(&self, slice: &AppSlice, ring: &Ring, fan: Option<Fan>) -> (f32, f32, f32) {
+        match fan {
+            None => (slice.start_angle, slice.end_angle, slice.angle),
+            Some(fan) => {
+                let origin = self.slices[ring.items.start].start_angle;
+                let start = fan.to_screen(origin, slice.start_angle);
+                let end = start + fan.scale(slice.end_angle - slice.start_angle);
+                (start, end, (start + end) / 2.0)
+            }
+        }
+    }
 }
 
 impl<'a> Program<Message> for PieCanvas<'a> {
@@ -604,29 +1787,15 @@ impl<'a> Program<Message> for PieCanvas<'a> {
             return None;
         };
 
-        let menu_size = self.menu_radius * 2.0 + self.icon_size as f32 + 80.0;
-
-        // Determine center point: cursor position or center of bounds
-        let center = if let Some((cx, cy)) = self.cursor_position {
-            // Clamp to keep menu fully visible (same logic as draw)
-            let half_menu = menu_size / 2.0;
-            // Handle case where screen is smaller than menu
-            let min_x = half_menu.min(bounds.width - half_menu);
-            let max_x = half_menu.max(bounds.width - half_menu);
-            let min_y = half_menu.min(bounds.height - half_menu);
-            let max_y = half_menu.max(bounds.height - half_menu);
-            let x = cx.clamp(min_x, max_x);
-            let y = cy.clamp(min_y, max_y);
-            Point::new(x, y)
-        } else {
-            Point::new(bounds.width / 2.0, bounds.height / 2.0)
-        };
+        // Determine center point: cursor position or center of bounds (same logic as draw)
+        let placement = self.placement(bounds);
+        let center = placement.center;
         let dx = cursor_pos.x - center.x;
         let dy = cursor_pos.y - center.y;
         let distance = (dx * dx + dy * dy).sqrt();
 
         // Check if in center (close button area)
-        if distance < self.inner_radius {
+        if distance < placement.inner_radius() {
             match event {
                 Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
                     return Some(cosmic::iced::widget::canvas::Action::publish(Message::CanvasEvent(PieCanvasMessage::ClickCenter)).and_capture());
@@ -640,7 +1809,7 @@ impl<'a> Program<Message> for PieCanvas<'a> {
         }
 
         // Check if outside the menu
-        if distance > self.menu_radius + 10.0 {
+        if distance > placement.menu_radius() + 10.0 {
             match event {
                 Event::Mouse(mouse::Event::CursorMoved { .. }) => {
                     return Some(cosmic::iced::widget::canvas::Action::publish(Message::CanvasEvent(PieCanvasMessage::HoverSegment(None))).and_capture());
@@ -651,27 +1820,17 @@ impl<'a> Program<Message> for PieCanvas<'a> {
         }
 
         // Calculate angle from center
-        let mut angle = dy.atan2(dx);
-
-        // Find which slice this angle falls into
-        let hovered_slice = self.slices.iter().find(|slice| {
-            let mut start = slice.start_angle;
-            let mut end = slice.end_angle;
-
-            // Normalize angles for comparison
-            while start > PI { start -= 2.0 * PI; }
-            while start < -PI { start += 2.0 * PI; }
-            while end > PI { end -= 2.0 * PI; }
-            while end < -PI { end += 2.0 * PI; }
-            while angle > PI { angle -= 2.0 * PI; }
-            while angle < -PI { angle += 2.0 * PI; }
-
-            // Handle wrap-around
-            if start > end {
-                angle >= start || angle <= end
-            } else {
-                angle >= start && angle <= end
-            }
+        let angle = dy.atan2(dx);
+
+        // Find which slice of the ring under the cursor this angle falls into
+        // (on a fanned pie, after mapping the angle back to the full circle)
+        let hovered_slice = ring_at_distance(&placement.rings, distance).and_then(|ring| {
+            let slices = &self.slices[ring.items.clone()];
+            let angle = match placement.fan {
+                Some(fan) => fan.to_layout(slices.first()?.start_angle, angle)?,
+                None => angle,
+            };
+            slice_at_angle(slices, angle)
         });
 
         match event {
@@ -703,27 +1862,14 @@ impl<'a> Program<Message> for PieCanvas<'a> {
         bounds: Rectangle,
         _cursor: mouse::Cursor,
     ) -> Vec<Geometry> {
-        let menu_size = self.menu_radius * 2.0 + self.icon_size as f32 + 80.0;
-
         use cosmic::iced::widget::canvas::Frame;
         let mut frame = Frame::new(renderer, bounds.size());
 
         {
             // Determine center point: cursor position or center of bounds
-            let center = if let Some((cx, cy)) = self.cursor_position {
-                // Clamp to keep menu fully visible
-                let half_menu = menu_size / 2.0;
-                // Handle case where screen is smaller than menu
-                let min_x = half_menu.min(bounds.width - half_menu);
-                let max_x = half_menu.max(bounds.width - half_menu);
-                let min_y = half_menu.min(bounds.height - half_menu);
-                let max_y = half_menu.max(bounds.height - half_menu);
-                let x = cx.clamp(min_x, max_x);
-                let y = cy.clamp(min_y, max_y);
-                Point::new(x, y)
-            } else {
-                Point::new(bounds.width / 2.0, bounds.height / 2.0)
-            };
+            let placement = self.placement(bounds);
+            let center = placement.center;
+            let fan = placement.fan;
             let theme = PieTheme::current();
 
             // Clear with transparent background
@@ -736,8 +1882,8 @@ impl<'a> Program<Message> for PieCanvas<'a> {
             // Draw background: transparent at inner edge, fading to solid, then fading to transparent at outer edge
             if self.show_background {
                 let bg_color = theme.bg_color;
-                let bg_outer = self.menu_radius + 2.0;
-                let bg_inner = self.inner_radius;
+                let bg_outer = placement.menu_radius() + 2.0;
+                let bg_inner = placement.inner_radius();
                 let bg_num_rings: usize = 60;
                 let bg_ring_width = (bg_outer - bg_inner) / bg_num_rings as f32;
 
@@ -760,7 +1906,7 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                     };
 
                     let ring_color = Color::from_rgba(bg_color.r, bg_color.g, bg_color.b, alpha);
-                    let ring_path = Path::circle(center, stroke_radius);
+                    let ring_path = circle_or_arc(center, stroke_radius, fan);
                     frame.stroke(
                         &ring_path,
                         Stroke::default()
@@ -770,29 +1916,34 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                 }
             }
 
-            // Draw ring for outer indicator area using theme color (only with background)
+            // Draw ring for outer indicator area of each ring using theme color (only with background)
             if self.show_background {
-                let indicator_ring_inner = self.menu_radius + 1.0;
-                let indicator_ring_outer = self.menu_radius + 4.0;
-                let indicator_ring_width = indicator_ring_outer - indicator_ring_inner;
-                let indicator_ring_radius = (indicator_ring_inner + indicator_ring_outer) / 2.0;
-                let indicator_bg = Path::circle(center, indicator_ring_radius);
-                frame.stroke(
-                    &indicator_bg,
-                    Stroke::default()
-                        .with_color(theme.indicator_ring_color)
-                        .with_width(indicator_ring_width),
-                );
+                for ring in &placement.rings {
+                    let indicator_ring_inner = ring.outer_radius + 1.0;
+                    let indicator_ring_outer = ring.outer_radius + 4.0;
+                    let indicator_ring_width = indicator_ring_outer - indicator_ring_inner;
+                    let indicator_ring_radius = (indicator_ring_inner + indicator_ring_outer) / 2.0;
+                    let indicator_bg = circle_or_arc(center, indicator_ring_radius, fan);
+                    frame.stroke(
+                        &indicator_bg,
+                        Stroke::default()
+                            .with_color(theme.indicator_ring_color)
+                            .with_width(indicator_ring_width),
+                    );
+                }
             }
 
             // Draw each slice segment with fade at inner edge
-            for slice in self.slices {
+            // Separators are left as empty gaps
+            for slice in self.slices.iter().filter(|slice| !slice.is_separator) {
+                let Some(ring) = ring_of(&placement.rings, slice.index) else { continue };
+                let (start_angle, end_angle, slice_angle) = self.slice_angles(slice, ring, fan);
                 let is_hovered = self.hovered == Some(slice.index);
 
                 // Only draw segments if show_background is enabled
                 if self.show_background {
-                    let outer_radius = self.menu_radius + 2.0;
-                    let inner_radius = self.inner_radius + 2.0;
+                    let outer_radius = ring.outer_radius + 2.0;
+                    let inner_radius = ring.inner_radius + 2.0;
                     let segment_depth = outer_radius - inner_radius;
 
                     // Base color for this segment
@@ -824,13 +1975,13 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                         // Draw arc for this ring
                         let arc = Path::new(|builder| {
                             let steps = 16;
-                            let angle_step = (slice.end_angle - slice.start_angle) / steps as f32;
+                            let angle_step = (end_angle - start_angle) / steps as f32;
                             builder.move_to(Point::new(
-                                center.x + ring_radius * slice.start_angle.cos(),
-                                center.y + ring_radius * slice.start_angle.sin(),
+                                center.x + ring_radius * start_angle.cos(),
+                                center.y + ring_radius * start_angle.sin(),
                             ));
                             for i in 1..=steps {
-                                let angle = slice.start_angle + angle_step * i as f32;
+                                let angle = start_angle + angle_step * i as f32;
                                 builder.line_to(Point::new(
                                     center.x + ring_radius * angle.cos(),
                                     center.y + ring_radius * angle.sin(),
@@ -847,8 +1998,38 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                     }
                 }
 
+                // Draw dwell progress as a fill growing outward from the inner edge
+                if is_hovered && self.dwell_progress > 0.0 {
+                    let inner_radius = ring.inner_radius + 2.0;
+                    let fill_radius = inner_radius + (ring.outer_radius - ring.inner_radius) * self.dwell_progress;
+                    let fill = Path::new(|builder| {
+                        let steps = 16;
+                        let angle_step = (end_angle - start_angle) / steps as f32;
+                        builder.move_to(Point::new(
+                            center.x + inner_radius * start_angle.cos(),
+                            center.y + inner_radius * start_angle.sin(),
+                        ));
+                        for i in 0..=steps {
+                            let angle = start_angle + angle_step * i as f32;
+                            builder.line_to(Point::new(
+                                center.x + fill_radius * angle.cos(),
+                                center.y + fill_radius * angle.sin(),
+                            ));
+                        }
+                        for i in (0..=steps).rev() {
+                            let angle = start_angle + angle_step * i as f32;
+                            builder.line_to(Point::new(
+                                center.x + inner_radius * angle.cos(),
+                                center.y + inner_radius * angle.sin(),
+                            ));
+                        }
+                        builder.close();
+                    });
+                    frame.fill(&fill, theme.dwell_fill_color);
+                }
+
                 // Calculate icon position using dynamic formula
-                let base_icon_radius = calculate_icon_radius(self.menu_radius, self.inner_radius, self.slices.len());
+                let base_icon_radius = calculate_icon_radius(ring.outer_radius, ring.inner_radius, ring.items.len());
 
                 // In icon_only_highlight mode, smoothly animate icon outward when hovered
                 // Rubber band effect: hovered icon moves out, neighbors get pulled out and toward it
@@ -857,11 +2038,13 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                     let radial = base_icon_radius + self.hover_offset * hover_offset;
 
                     // Angular pull: neighbors get pulled toward the hovered icon
-                    let angular = if let Some(hovered_idx) = self.hovered {
-                        let n = self.slices.len();
-                        let dist = circular_distance(slice.index, hovered_idx, n);
+                    // (only on the hovered icon's own ring)
+                    let angular = if let Some(hovered_idx) = self.hovered.filter(|i| ring.items.contains(i)) {
+                        let n = ring.items.len();
+                        let (from, toward) = (slice.index - ring.items.start, hovered_idx - ring.items.start);
+                        let dist = circular_distance(from, toward, n);
                         if dist > 0 && dist < RUBBER_BAND_ANGULAR_FALLOFF.len() {
-                            let direction = circular_direction(slice.index, hovered_idx, n);
+                            let direction = circular_direction(from, toward, n);
                             let falloff = RUBBER_BAND_ANGULAR_FALLOFF[dist];
                             direction * RUBBER_BAND_ANGULAR_PULL * falloff * hover_offset
                         } else {
@@ -871,9 +2054,9 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                         0.0
                     };
 
-                    (radial, slice.angle + angular)
+                    (radial, slice_angle + angular)
                 } else {
-                    (base_icon_radius, slice.angle)
+                    (base_icon_radius, slice_angle)
                 };
 
                 let icon_center = Point::new(
@@ -940,12 +2123,34 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                     });
                 }
 
+                // Draw submenu marker (small outward-pointing triangle at the outer edge)
+                if slice.is_submenu {
+                    let tip_radius = ring.outer_radius - 2.0;
+                    let base_radius = tip_radius - 7.0;
+                    let half_angle = 5.0 / base_radius;
+                    let marker = Path::new(|builder| {
+                        builder.move_to(Point::new(
+                            center.x + tip_radius * slice_angle.cos(),
+                            center.y + tip_radius * slice_angle.sin(),
+                        ));
+                        for angle in [slice_angle - half_angle, slice_angle + half_angle] {
+                            builder.line_to(Point::new(
+                                center.x + base_radius * angle.cos(),
+                                center.y + base_radius * angle.sin(),
+                            ));
+                        }
+                        builder.close();
+                    });
+                    let marker_color = Color { a: 0.7, ..theme.text_color };
+                    frame.fill(&marker, marker_color);
+                }
+
                 // Draw running indicator (arc at outer edge)
                 // Arc length varies based on number of running instances (like COSMIC dock)
                 if slice.running_count > 0 {
-                    let arc_radius = self.menu_radius + 4.0;
-                    let slice_span = slice.end_angle - slice.start_angle;
-                    let slice_center = (slice.start_angle + slice.end_angle) / 2.0;
+                    let arc_radius = ring.outer_radius + 4.0;
+                    let slice_span = end_angle - start_angle;
+                    let slice_center = (start_angle + end_angle) / 2.0;
 
                     // Calculate arc length based on running count:
                     // 1 window = small dot (12% of slice)
@@ -1009,60 +2214,8 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                 let padding_y = 10.0;
                 let pill_width = text_width + padding_x * 2.0;
                 let pill_height = total_height + padding_y * 2.0;
-                let pill_radius = pill_height / 2.0; // Fully rounded ends
-
-                let pill = Path::new(|builder| {
-                    // Draw rounded rectangle (pill shape)
-                    let left = center.x - pill_width / 2.0;
-                    let right = center.x + pill_width / 2.0;
-                    let top = center.y - pill_height / 2.0;
-                    let bottom = center.y + pill_height / 2.0;
-                    let r = pill_radius.min(pill_width / 2.0);
-
-                    // Start at top-left after the curve
-                    builder.move_to(Point::new(left + r, top));
-                    // Top edge
-                    builder.line_to(Point::new(right - r, top));
-                    // Top-right curve (approximate with lines)
-                    for i in 0..=8 {
-                        let angle = -PI / 2.0 + (i as f32 / 8.0) * (PI / 2.0);
-                        builder.line_to(Point::new(
-                            right - r + r * angle.cos(),
-                            top + r + r * angle.sin(),
-                        ));
-                    }
-                    // Right edge
-                    builder.line_to(Point::new(right, bottom - r));
-                    // Bottom-right curve
-                    for i in 0..=8 {
-                        let angle = 0.0 + (i as f32 / 8.0) * (PI / 2.0);
-                        builder.line_to(Point::new(
-                            right - r + r * angle.cos(),
-                            bottom - r + r * angle.sin(),
-                        ));
-                    }
-                    // Bottom edge
-                    builder.line_to(Point::new(left + r, bottom));
-                    // Bottom-left curve
-                    for i in 0..=8 {
-                        let angle = PI / 2.0 + (i as f32 / 8.0) * (PI / 2.0);
-                        builder.line_to(Point::new(
-                            left + r + r * angle.cos(),
-                            bottom - r + r * angle.sin(),
-                        ));
-                    }
-                    // Left edge
-                    builder.line_to(Point::new(left, top + r));
-                    // Top-left curve
-                    for i in 0..=8 {
-                        let angle = PI + (i as f32 / 8.0) * (PI / 2.0);
-                        builder.line_to(Point::new(
-                            left + r + r * angle.cos(),
-                            top + r + r * angle.sin(),
-                        ));
-                    }
-                    builder.close();
-                });
+
+                let pill = pill_path(center, pill_width, pill_height);
 
                 // Semi-transparent dark background
                 let pill_color = Color::from_rgba(0.0, 0.0, 0.0, 0.7);
@@ -1083,9 +2236,34 @@ impl<'a> Program<Message> for PieCanvas<'a> {
                 }
             }
 
+            // Draw the search below the ring
+            if !self.query.is_empty() {
+                let content = if self.slices.is_empty() {
+                    format!("No matches for \u{201c}{}\u{201d}", self.query)
+                } else {
+                    self.query.to_string()
+                };
+                let font_size = 16.0;
+                let text_width = (content.chars().count() as f32 * font_size * 0.6).max(60.0);
+                // Kept on screen when a fanned pie sits at the bottom edge
+                let y = (center.y + placement.menu_radius() + 30.0).min(bounds.height - 30.0);
+                let position = Point::new(center.x, y);
+                frame.fill(&pill_path(position, text_width + 32.0, 40.0), Color::from_rgba(0.0, 0.0, 0.0, 0.7));
+                frame.fill_text(Text {
+                    content,
+                    position,
+                    color: Color::WHITE,
+                    size: font_size.into(),
+                    font: Font::DEFAULT,
+                    align_x: Horizontal::Center.into(),
+                    align_y: Vertical::Center,
+                    ..Text::default()
+                });
+            }
+
             // Only draw outer border if background is shown
             if self.show_background {
-                let outer_border = Path::circle(center, self.menu_radius + 5.0);
+                let outer_border = circle_or_arc(center, placement.menu_radius() + 5.0, fan);
                 frame.stroke(
                     &outer_border,
                     Stroke::default()
@@ -1126,7 +2304,7 @@ fn app_style(_state: &PieMenuApp, _theme: &Theme) -> cosmic::iced::theme::Style
 pub fn show_pie_menu_at(apps: Vec<AppInfo>, position: Option<(f32, f32)>) {
     println!("Launching pie menu with {} apps at {:?}", apps.len(), position);
 
-    let _ = cosmic::iced::daemon(move || PieMenuApp::new_at(apps.clone(), position), PieMenuApp::update, PieMenuApp::view)
+    let _ = cosmic::iced::daemon(move || PieMenuApp::new_at(apps.clone(), position, Host::Process), PieMenuApp::update, PieMenuApp::view)
         .subscription(PieMenuApp::subscription)
         .theme(PieMenuApp::theme)
         .style(app_style)
@@ -1154,10 +2332,33 @@ struct CursorTracker {
     tick_count: u32,
     /// Shared cursor position from draw() method
     cursor_pos: Arc<Mutex<Option<(f32, f32)>>>,
+    /// Menu to show once the position is captured
+    menu: String,
+    /// What to do once the position is captured or tracking is cancelled
+    host: Host,
+    /// The overlay's layer surface
+    surface: Id,
+    /// Set once the overlay has closed (service host only)
+    closed: bool,
+    /// Where the cursor was captured (service host only)
+    captured_at: Option<(f32, f32)>,
+    /// Dark theme, read once when tracking starts
+    dark_mode: bool,
+}
+
+/// Spawn the pie menu process at a captured position
+fn spawn_pie_at(x: f32, y: f32, menu: &str) {
+    let exe = std::env::current_exe().unwrap_or_else(|_| "cosmic-pie-menu".into());
+    let _ = Command::new(exe)
+        .arg("--pie-at")
+        .arg(format!("{}", x))
+        .arg(format!("{}", y))
+        .args(["--menu", menu])
+        .spawn();
 }
 
 impl CursorTracker {
-    fn new() -> (Self, Task<TrackerMessage>) {
+    fn new(menu: String, host: Host) -> (Self, Task<TrackerMessage>) {
         // Create a full-screen layer surface at overlay level
         let mut settings = SctkLayerSurfaceSettings::default();
         settings.keyboard_interactivity = KeyboardInteractivity::Exclusive;
@@ -1173,11 +2374,38 @@ impl CursorTracker {
             captured: false,
             tick_count: 0,
             cursor_pos: Arc::new(Mutex::new(None)),
+            menu,
+            host,
+            surface: settings.id,
+            closed: false,
+            captured_at: None,
+            dark_mode: is_dark_mode(),
         };
 
         (tracker, get_layer_surface(settings))
     }
 
+    /// Open the menu at the captured position and close the overlay
+    fn place(&mut self, x: f32, y: f32) -> Task<TrackerMessage> {
+        self.captured = true;
+        if self.host == Host::Process {
+            // Spawn a new process with the position, then exit the tracker
+            spawn_pie_at(x, y, &self.menu);
+            std::process::exit(0);
+        }
+        self.captured_at = Some((x, y));
+        self.close()
+    }
+
+    /// Close the overlay: exit when it has its own process, else remove its surface
+    fn close(&mut self) -> Task<TrackerMessage> {
+        if self.host == Host::Process {
+            std::process::exit(0);
+        }
+        self.closed = true;
+        destroy_layer_surface(self.surface)
+    }
+
     fn title(&self, _id: Id) -> String {
         String::from("Cursor Tracker")
     }
@@ -1186,42 +2414,21 @@ impl CursorTracker {
         match message {
             TrackerMessage::CursorCaptured(x, y) => {
                 if !self.captured {
-                    self.captured = true;
                     println!("Cursor captured at ({}, {})", x, y);
-
-                    // Spawn a new process with the position
-                    let exe = std::env::current_exe().unwrap_or_else(|_| "cosmic-pie-menu".into());
-                    let _ = Command::new(exe)
-                        .arg("--pie-at")
-                        .arg(format!("{}", x))
-                        .arg(format!("{}", y))
-                        .spawn();
-
-                    // Exit the tracker
-                    std::process::exit(0);
+                    return self.place(x, y);
                 }
                 Task::none()
             }
-            TrackerMessage::Cancel => {
-                std::process::exit(0);
-            }
+            TrackerMessage::Cancel => self.close(),
             TrackerMessage::Tick => {
                 self.tick_count += 1;
 
                 // Check if cursor position was captured from draw()
                 if !self.captured {
-                    if let Ok(guard) = self.cursor_pos.lock() {
-                        if let Some((x, y)) = *guard {
-                            self.captured = true;
-                            println!("Cursor captured from draw at ({}, {})", x, y);
-                            let exe = std::env::current_exe().unwrap_or_else(|_| "cosmic-pie-menu".into());
-                            let _ = Command::new(exe)
-                                .arg("--pie-at")
-                                .arg(format!("{}", x))
-                                .arg(format!("{}", y))
-                                .spawn();
-                            std::process::exit(0);
-                        }
+                    let drawn = self.cursor_pos.lock().ok().and_then(|guard| *guard);
+                    if let Some((x, y)) = drawn {
+                        println!("Cursor captured from draw at ({}, {})", x, y);
+                        return self.place(x, y);
                     }
                 }
 
@@ -1293,7 +2500,7 @@ impl CursorTracker {
     }
 
     fn theme(&self, _id: Id) -> Theme {
-        if is_dark_mode() {
+        if self.dark_mode {
             Theme::Dark
         } else {
             Theme::Light
@@ -1382,13 +2589,437 @@ fn tracker_style(_state: &CursorTracker, _theme: &Theme) -> cosmic::iced::theme:
 }
 
 /// Launch the pie menu with cursor tracking
-/// Shows an invisible full-screen overlay to capture cursor position first
-pub fn show_pie_menu_with_tracking(_apps: Vec<AppInfo>) {
+/// Shows an invisible full-screen overlay to capture cursor position first,
+/// then opens the named menu there
+pub fn show_pie_menu_with_tracking(menu: String) {
     println!("Starting cursor tracking overlay...");
 
-    let _ = cosmic::iced::daemon(CursorTracker::new, CursorTracker::update, CursorTracker::view)
+    let _ = cosmic::iced::daemon(move || CursorTracker::new(menu.clone(), Host::Process), CursorTracker::update, CursorTracker::view)
         .subscription(CursorTracker::subscription)
         .theme(CursorTracker::theme)
         .style(tracker_style)
         .run();
 }
+
+// ============================================================================
+// Pie Service (--daemon)
+// ============================================================================
+
+/// Process-global handle to the service's request receiver
+/// (`Subscription::run` takes a non-capturing `fn`, as in the applet)
+static REQUEST_RX: OnceLock<
+    Arc<tokio::sync::Mutex<tokio::sync::mpsc::UnboundedReceiver<daemon::Request>>>,
+> = OnceLock::new();
+
+/// Messages for the pie service
+#[derive(Debug, Clone)]
+enum ServiceMessage {
+    /// A request arrived on the service socket or over D-Bus
+    Request(daemon::Request),
+    /// Message for the open pie
+    Pie(Message),
+    /// Message for the cursor tracking overlay
+    Tracker(TrackerMessage),
+}
+
+/// Long-lived process that hosts the tracker and pie surfaces on request
+struct PieService {
+    /// Loads the items for a menu by name
+    load_apps: fn(&str) -> Vec<AppInfo>,
+    /// The pie on screen, if any
+    pie: Option<PieMenuApp>,
+    /// Cursor tracking overlay waiting to place a pie, if any
+    tracker: Option<CursorTracker>,
+    /// Marking-menu release that arrived while the tracker was still up
+    pending_release: Option<Option<f32>>,
+}
+
+impl PieService {
+    fn new(load_apps: fn(&str) -> Vec<AppInfo>) -> (Self, Task<ServiceMessage>) {
+        let service = Self {
+            load_apps,
+            pie: None,
+            tracker: None,
+            pending_release: None,
+        };
+        (service, Task::none())
+    }
+
+    /// Remove whatever is on screen, so a new request starts fresh
+    fn close_all(&mut self) -> Task<ServiceMessage> {
+        self.pending_release = None;
+        let surfaces: Vec<Id> = self.pie.take().map(|pie| pie.surface)
+            .into_iter()
+            .chain(self.tracker.take().map(|tracker| tracker.surface))
+            .collect();
+        if !surfaces.is_empty() {
+            dbus::emit(dbus::Event::Closed);
+        }
+        Task::batch(surfaces.into_iter().map(destroy_layer_surface))
+    }
+
+    /// Load a menu and show it as a pie
+    fn open_pie(&mut self, position: Option<(f32, f32)>, menu: &str) -> Task<ServiceMessage> {
+        let apps = (self.load_apps)(menu);
+        if apps.is_empty() && menu == menus::WINDOWS_MENU {
+            println!("No open windows");
+            return Task::none();
+        }
+        println!("Showing pie menu with {} apps at {:?}", apps.len(), position);
+        let (mut pie, open) = PieMenuApp::new_at(apps, position, Host::Service);
+
+        // A flick that was already released picks its slice without showing the pie
+        if let Some(angle) = self.pending_release.take() {
+            let task = pie.update(Message::Release(angle));
+            if pie.closed {
+                dbus::emit(dbus::Event::Closed);
+                return Task::none();
+            }
+            self.pie = Some(pie);
+            return Task::batch([open.map(ServiceMessage::Pie), task.map(ServiceMessage::Pie)]);
+        }
+
+        self.pie = Some(pie);
+        open.map(ServiceMessage::Pie)
+    }
+
+    /// Handle a marking-menu release for whatever the service is showing
+    fn release(&mut self, angle: Option<f32>) -> Task<ServiceMessage> {
+        if self.pie.is_some() {
+            self.update(ServiceMessage::Pie(Message::Release(angle)))
+        } else {
+            if self.tracker.is_some() {
+                self.pending_release = Some(angle);
+            }
+            Task::none()
+        }
+    }
+
+    fn update(&mut self, message: ServiceMessage) -> Task<ServiceMessage> {
+        match message {
+            ServiceMessage::Request(request) => {
+                println!("Pie service request: {}", request.to_line());
+                match request {
+                    daemon::Request::Reload => {
+                        desktop_index::rebuild();
+                        clear_icon_cache();
+                        return Task::none();
+                    }
+                    daemon::Request::Release { angle } => return self.release(angle),
+                    daemon::Request::Point { angle } => {
+                        return self.update(ServiceMessage::Pie(Message::Point(angle)));
+                    }
+                    _ => {}
+                }
+                let close = self.close_all();
+                let open = match request {
+                    daemon::Request::Show { menu } => {
+                        let (tracker, task) = CursorTracker::new(menu, Host::Service);
+                        self.tracker = Some(tracker);
+                        task.map(ServiceMessage::Tracker)
+                    }
+                    daemon::Request::ShowAt { x, y, menu } => self.open_pie(Some((x, y)), &menu),
+                    daemon::Request::Hide
+                    | daemon::Request::Reload
+                    | daemon::Request::Release { .. }
+                    | daemon::Request::Point { .. } => Task::none(),
+                };
+                Task::batch([close, open])
+            }
+            ServiceMessage::Pie(message) => {
+                let Some(pie) = self.pie.as_mut() else {
+                    return Task::none();
+                };
+                let task = pie.update(message).map(ServiceMessage::Pie);
+                if pie.closed {
+                    self.pie = None;
+                    dbus::emit(dbus::Event::Closed);
+                }
+                task
+            }
+            ServiceMessage::Tracker(message) => {
+                let Some(tracker) = self.tracker.as_mut() else {
+                    return Task::none();
+                };
+                let task = tracker.update(message).map(ServiceMessage::Tracker);
+                if !tracker.closed {
+                    return task;
+                }
+                let placed = tracker.captured_at.map(|position| (position, tracker.menu.clone()));
+                self.tracker = None;
+                match placed {
+                    Some((position, menu)) => Task::batch([task, self.open_pie(Some(position), &menu)]),
+                    None => {
+                        dbus::emit(dbus::Event::Closed);
+                        task
+                    }
+                }
+            }
+        }
+    }
+
+    fn subscription(&self) -> Subscription<ServiceMessage> {
+        // Only emit messages when a request actually arrives (no polling)
+        let requests = Subscription::run(|| async_stream::stream! {
+            let rx = REQUEST_RX.get().unwrap().clone();
+            let mut rx = rx.lock().await;
+            while let Some(request) = rx.recv().await {
+                yield request;
+            }
+        })
+        .map(ServiceMessage::Request);
+
+        let mut subscriptions = vec![requests];
+        if let Some(pie) = &self.pie {
+            subscriptions.push(pie.subscription().map(ServiceMessage::Pie));
+        }
+        if let Some(tracker) = &self.tracker {
+            subscriptions.push(tracker.subscription().map(ServiceMessage::Tracker));
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    fn view(&self, id: Id) -> Element<'_, ServiceMessage> {
+        match (&self.pie, &self.tracker) {
+            (Some(pie), _) if pie.surface == id => pie.view(id).map(ServiceMessage::Pie),
+            (_, Some(tracker)) if tracker.surface == id => tracker.view(id).map(ServiceMessage::Tracker),
+            // A surface that is being destroyed
+            _ => cosmic::iced::widget::text("").into(),
+        }
+    }
+
+    fn theme(&self, id: Id) -> Theme {
+        match (&self.pie, &self.tracker) {
+            (Some(pie), _) => pie.theme(id),
+            (_, Some(tracker)) => tracker.theme(id),
+            _ => Theme::Dark,
+        }
+    }
+}
+
+/// Style for the service: the tracker's tint while tracking, else transparent
+fn service_style(state: &PieService, theme: &Theme) -> cosmic::iced::theme::Style {
+    match (&state.tracker, &state.pie) {
+        (Some(tracker), _) => tracker_style(tracker, theme),
+        (_, Some(pie)) => app_style(pie, theme),
+        _ => cosmic::iced::theme::Style {
+            background_color: Color::TRANSPARENT,
+            text_color: Color::WHITE,
+            icon_color: Color::WHITE,
+        },
+    }
+}
+
+/// Run the pie service until the process is killed
+///
+/// Listens on the service socket (see [`daemon`]) and the session bus (see
+/// [`dbus`]) and shows pies for each request, loading menus with `load_apps`.
+/// Icon lookups are cached across opens.
+pub fn run_pie_service(load_apps: fn(&str) -> Vec<AppInfo>) {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    if let Err(e) = daemon::start_listener(tx.clone()) {
+        eprintln!("Failed to start the pie service: {}", e);
+        return;
+    }
+    dbus::start(tx, load_apps);
+    let _ = REQUEST_RX.set(Arc::new(tokio::sync::Mutex::new(rx)));
+    println!("Pie service running");
+
+    let _ = cosmic::iced::daemon(move || PieService::new(load_apps), PieService::update, PieService::view)
+        .subscription(PieService::subscription)
+        .theme(PieService::theme)
+        .style(service_style)
+        .run();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(names: &[&str]) -> Vec<AppInfo> {
+        names
+            .iter()
+            .map(|name| match *name {
+                "-" => AppInfo::separator(),
+                name => AppInfo::command(name, "true", None),
+            })
+            .collect()
+    }
+
+    /// Names of items, to compare orderings
+    fn names(apps: &[AppInfo]) -> Vec<&str> {
+        apps.iter().map(|app| app.name.as_str()).collect()
+    }
+
+    /// A pie showing `items(names)`, hosted by the service so closing it
+    /// doesn't exit the test
+    fn test_pie(names: &[&str]) -> PieMenuApp {
+        PieMenuApp::with_config(items(names), None, Host::Service, &PieMenuConfig::default(), false).0
+    }
+
+    #[test]
+    fn test_keyboard_selection() {
+        let apps = items(&["Files", "-", "Firefox", "Terminal"]);
+
+        assert_eq!(step_selection(&apps, None, true), Some(0));
+        assert_eq!(step_selection(&apps, None, false), Some(3));
+        assert_eq!(step_selection(&apps, Some(0), true), Some(2));
+        assert_eq!(step_selection(&apps, Some(2), false), Some(0));
+        assert_eq!(step_selection(&apps, Some(3), true), Some(0));
+        assert_eq!(step_selection(&[], None, true), None);
+
+        assert_eq!(nth_selectable(&apps, 1), Some(2));
+        assert_eq!(nth_selectable(&apps, 3), None);
+    }
+
+    #[test]
+    fn test_slice_at_angle() {
+        // Four slices starting at the top, going clockwise, with a separator on the left
+        let slices: Vec<AppSlice> = (0..4)
+            .map(|index| {
+                let angle = -PI / 2.0 + index as f32 * PI / 2.0;
+                AppSlice {
+                    index,
+                    name: String::new(),
+                    icon_path: None,
+                    angle,
+                    start_angle: angle - PI / 4.0,
+                    end_angle: angle + PI / 4.0,
+                    running_count: 0,
+                    glow_handle: None,
+                    is_submenu: false,
+                    is_separator: index == 3,
+                }
+            })
+            .collect();
+        let at = |angle: f32| slice_at_angle(&slices, angle).map(|slice| slice.index);
+
+        assert_eq!(at(-PI / 2.0), Some(0)); // up
+        assert_eq!(at(0.1), Some(1)); // right
+        assert_eq!(at(PI / 2.0), Some(2)); // down
+        assert_eq!(at(PI), None); // left, the separator
+        assert_eq!(at(-PI / 2.0 + 2.0 * PI), Some(0)); // angles wrap
+    }
+
+    #[test]
+    fn test_layout_ring() {
+        // Spans in whole degrees clockwise from the top
+        let degrees = |spans: &[(f32, f32)]| {
+            spans
+                .iter()
+                .map(|&(s, e)| ((s + PI / 2.0).to_degrees().round() as i32, (e + PI / 2.0).to_degrees().round() as i32))
+                .collect::<Vec<_>>()
+        };
+
+        let (apps, spans) = layout_ring(items(&["A", "B", "C", "D"]), &BTreeMap::new());
+        assert_eq!(names(&apps), ["A", "B", "C", "D"]);
+        assert_eq!(degrees(&spans), [(-45, 45), (45, 135), (135, 225), (225, 315)]);
+
+        // D north and A south; B and C fill the gaps in order
+        let pins = BTreeMap::from([
+            ("command:D".to_string(), config::pin_angle("north").unwrap()),
+            ("command:A".to_string(), config::pin_angle("south").unwrap()),
+        ]);
+        let (apps, spans) = layout_ring(items(&["A", "B", "C", "D"]), &pins);
+        assert_eq!(names(&apps), ["D", "B", "A", "C"]);
+        assert_eq!(degrees(&spans), [(-45, 45), (45, 135), (135, 225), (225, 315)]);
+
+        // Close pins narrow each other; C is too close to A, so it is unpinned
+        let pins = BTreeMap::from([
+            ("command:A".to_string(), config::pin_angle("north").unwrap()),
+            ("command:B".to_string(), config::pin_angle("60").unwrap()),
+            ("command:C".to_string(), config::pin_angle("10").unwrap()),
+        ]);
+        let (apps, spans) = layout_ring(items(&["A", "B", "C", "D"]), &pins);
+        assert_eq!(names(&apps), ["A", "B", "C", "D"]);
+        assert_eq!(degrees(&spans), [(-30, 30), (30, 90), (90, 210), (210, 330)]);
+    }
+
+    #[test]
+    fn test_split_rings() {
+        let ring_names = |rings: Vec<Vec<AppInfo>>| rings.iter().map(|ring| names(ring).join(" ")).collect::<Vec<_>>();
+        // C and D are running apps that aren't favorites
+        let mixed = || {
+            let mut apps = items(&["A", "B", "-", "C", "D"]);
+            apps[3].is_favorite = false;
+            apps[4].is_favorite = false;
+            apps
+        };
+
+        assert_eq!(ring_names(split_rings(mixed(), 1, 12)), ["A B  C D"]);
+        assert_eq!(ring_names(split_rings(mixed(), 2, 12)), ["A B", "C D"]);
+        // Overflow fills the next ring; the outermost takes the rest
+        assert_eq!(ring_names(split_rings(mixed(), 2, 1)), ["A", "B  C D"]);
+        assert_eq!(ring_names(split_rings(mixed(), 3, 1)), ["A", "B", "C D"]);
+        assert_eq!(ring_names(split_rings(items(&["A", "B"]), 3, 12)), ["A B"]);
+
+        let rings = build_rings(&[2, 2], 48, 75.0, 2.0 * PI);
+        assert_eq!((rings[0].items.clone(), rings[1].items.clone()), (0..2, 2..4));
+        assert_eq!(rings[0].outer_radius, MIN_MENU_RADIUS);
+        assert_eq!(rings[1].inner_radius, MIN_MENU_RADIUS + RING_GAP);
+        assert_eq!(ring_at_distance(&rings, MIN_MENU_RADIUS + 1.0).map(|r| r.items.start), Some(0));
+        assert_eq!(ring_at_distance(&rings, MIN_MENU_RADIUS + 20.0).map(|r| r.items.start), Some(2));
+        assert_eq!(ring_distance(&rings, 0, 1), Some(1));
+        assert_eq!(ring_distance(&rings, 1, 2), None);
+    }
+
+    #[test]
+    fn test_fan_at_edges() {
+        let fan = |x, y| fan_at(x, y, 1920.0, 1080.0, |_| 150.0);
+        assert_eq!(fan(960.0, 540.0), None);
+        assert_eq!(fan(960.0, 1070.0), Some(Fan { start: PI, span: PI })); // bottom: upper half
+        assert_eq!(fan(10.0, 1070.0), Some(Fan { start: -PI / 2.0, span: PI / 2.0 })); // bottom-left
+        assert_eq!(fan_at(100.0, 100.0, 200.0, 1080.0, |_| 150.0), None); // crosses both sides
+
+        // A fanned pie grows: a half fan at the bottom reaching the left edge becomes a corner
+        let growing = |x, y| fan_at(x, y, 1920.0, 1080.0, |span| 150.0 * 2.0 * PI / span);
+        assert_eq!(growing(960.0, 1070.0), Some(Fan { start: PI, span: PI }));
+        assert_eq!(growing(250.0, 1070.0), Some(Fan { start: -PI / 2.0, span: PI / 2.0 }));
+        let short = fan_at(250.0, 590.0, 1920.0, 700.0, |span| 150.0 * 2.0 * PI / span);
+        assert_eq!(short, None); // the quarter would reach the top
+
+        // A four-slice ring fanned over the upper half: west, northwest, northeast, east
+        let fan = Fan { start: PI, span: PI };
+        let origin = -PI / 2.0 - PI / 4.0;
+        let first = fan.to_screen(origin, origin);
+        assert!((first - PI).abs() < 1e-5);
+        assert!((fan.scale(PI / 2.0) - PI / 4.0).abs() < 1e-5);
+        let back = fan.to_layout(origin, -PI / 2.0 + 0.1).unwrap(); // just right of north
+        assert!((back - (origin + PI + 0.2)).abs() < 1e-5);
+        assert_eq!(fan.to_layout(origin, PI / 2.0), None); // south is off the arc
+    }
+
+    #[test]
+    fn test_dwell_needs_pointer() {
+        let mut pie = test_pie(&["Files", "Firefox", "Terminal"]);
+        pie.dwell_time = Some(Duration::from_secs(60));
+
+        // Keyboard and search highlights don't start the timer
+        pie.move_selection(true);
+        let _ = pie.update(Message::Tick);
+        assert_eq!(pie.dwell, None);
+        pie.set_query("fi".to_string());
+        assert!(pie.hovered_slice.is_some());
+        let _ = pie.update(Message::Tick);
+        assert_eq!(pie.dwell, None);
+
+        // The pointer does, and the keyboard moving away stops it
+        let _ = pie.update(Message::CanvasEvent(PieCanvasMessage::HoverSegment(Some(1))));
+        let _ = pie.update(Message::Tick);
+        assert!(matches!(pie.dwell, Some((1, Some(_)))));
+        pie.move_selection(true);
+        let _ = pie.update(Message::Tick);
+        assert_eq!(pie.dwell, None);
+    }
+
+    #[test]
+    fn test_search_items() {
+        let mut apps = items(&["Terminal", "-", "Firefox"]);
+        apps.push(AppInfo::submenu("Office", None, items(&["Writer", "Files"])));
+
+        // Name matches first, then the rest in ring order; submenus are searched
+        assert_eq!(names(&search_items(&apps, "fi")), ["Firefox", "Files", "Office"]);
+        assert_eq!(names(&search_items(&apps, "writ")), ["Writer"]);
+        assert!(search_items(&apps, "zzz").is_empty());
+    }
+}
@@ -1,19 +1,12 @@
 //! CLI settings protocol for cosmic-applet-settings hub integration.
 
-use crate::config::{PieMenuConfig, SwipeAction, WorkspaceLayout, read_workspace_layout};
+use crate::config::{DwellAction, PieMenuConfig, RunningPlacement, SwipeAction, WorkspaceLayout, read_workspace_layout};
+use crate::menus;
 
 pub fn describe() {
     let config = PieMenuConfig::load();
     let layout = read_workspace_layout();
 
-    let swipe_options = serde_json::json!([
-        {"value": "None", "label": "None (System Default)"},
-        {"value": "AppLibrary", "label": "App Library"},
-        {"value": "Launcher", "label": "Launcher"},
-        {"value": "Workspaces", "label": "Workspaces"},
-        {"value": "PieMenu", "label": "Pie Menu"}
-    ]);
-
     let mut swipe_items = vec![];
     match layout {
         WorkspaceLayout::Horizontal => {
@@ -21,15 +14,15 @@ pub fn describe() {
                 "type": "select",
                 "key": "swipe_up",
                 "label": "Swipe Up",
-                "value": swipe_to_str(config.swipe_up),
-                "options": swipe_options
+                "value": swipe_to_str(&config.swipe_up),
+                "options": swipe_options(&config.swipe_up)
             }));
             swipe_items.push(serde_json::json!({
                 "type": "select",
                 "key": "swipe_down",
                 "label": "Swipe Down",
-                "value": swipe_to_str(config.swipe_down),
-                "options": swipe_options
+                "value": swipe_to_str(&config.swipe_down),
+                "options": swipe_options(&config.swipe_down)
             }));
         }
         WorkspaceLayout::Vertical => {
@@ -37,15 +30,15 @@ pub fn describe() {
                 "type": "select",
                 "key": "swipe_left",
                 "label": "Swipe Left",
-                "value": swipe_to_str(config.swipe_left),
-                "options": swipe_options
+                "value": swipe_to_str(&config.swipe_left),
+                "options": swipe_options(&config.swipe_left)
             }));
             swipe_items.push(serde_json::json!({
                 "type": "select",
                 "key": "swipe_right",
                 "label": "Swipe Right",
-                "value": swipe_to_str(config.swipe_right),
-                "options": swipe_options
+                "value": swipe_to_str(&config.swipe_right),
+                "options": swipe_options(&config.swipe_right)
             }));
         }
     }
@@ -61,6 +54,58 @@ pub fn describe() {
         "unit": ""
     }));
 
+    let menu_names = menus::menu_choices(&config);
+    let menu_options: Vec<serde_json::Value> = menu_names
+        .iter()
+        .map(|n| serde_json::json!({"value": n, "label": n}))
+        .collect();
+
+    let mut menu_items: Vec<serde_json::Value> = [
+        ("tap_menu", "Tap Gesture", &config.tap_menu),
+        ("swipe_menu", "Swipe Gesture", &config.swipe_menu),
+        ("middle_click_menu", "Middle Click", &config.middle_click_menu),
+        ("applet_menu", "Panel Applet", &config.applet_menu),
+    ]
+    .into_iter()
+    .map(|(key, label, value)| {
+        serde_json::json!({
+            "type": "select",
+            "key": key,
+            "label": label,
+            "value": value,
+            "options": menu_options
+        })
+    })
+    .collect();
+
+    menu_items.push(serde_json::json!({
+        "type": "toggle",
+        "key": "smart_order",
+        "label": "Order by Use",
+        "value": config.smart_order
+    }));
+    menu_items.push(serde_json::json!({
+        "type": "select",
+        "key": "running_placement",
+        "label": "Running Apps",
+        "value": running_placement_to_str(config.running_placement),
+        "options": [
+            {"value": "FirstSeen", "label": "In Order Opened"},
+            {"value": "Alphabetical", "label": "By Name"},
+            {"value": "Separate", "label": "Own Section"}
+        ]
+    }));
+    menu_items.push(serde_json::json!({
+        "type": "slider",
+        "key": "suggested_count",
+        "label": "Suggested Apps",
+        "value": config.suggested_count as f64,
+        "min": 0.0,
+        "max": 8.0,
+        "step": 1.0,
+        "unit": ""
+    }));
+
     let schema = serde_json::json!({
         "title": "Pie Menu Settings",
         "description": "Configure gesture detection and appearance for the radial app launcher.",
@@ -103,6 +148,18 @@ pub fn describe() {
                         "key": "middle_click_trigger",
                         "label": "Middle Click Trigger",
                         "value": config.middle_click_trigger
+                    },
+                    {
+                        "type": "toggle",
+                        "key": "marking_menu",
+                        "label": "Marking Menu (hold, move, release)",
+                        "value": config.marking_menu
+                    },
+                    {
+                        "type": "toggle",
+                        "key": "touchpad_select",
+                        "label": "Touchpad Selection (drag after the tap)",
+                        "value": config.touchpad_select
                     }
                 ]
             },
@@ -110,6 +167,10 @@ pub fn describe() {
                 "title": "Swipe Actions",
                 "items": swipe_items
             },
+            {
+                "title": "Menus",
+                "items": menu_items
+            },
             {
                 "title": "Appearance",
                 "items": [
@@ -145,6 +206,32 @@ pub fn describe() {
                         "step": 5.0,
                         "unit": ""
                     },
+                    {
+                        "type": "toggle",
+                        "key": "fan_at_edges",
+                        "label": "Fan Out at Screen Edges",
+                        "value": config.fan_at_edges
+                    },
+                    {
+                        "type": "slider",
+                        "key": "max_rings",
+                        "label": "Rings",
+                        "value": config.max_rings as f64,
+                        "min": 1.0,
+                        "max": 3.0,
+                        "step": 1.0,
+                        "unit": ""
+                    },
+                    {
+                        "type": "slider",
+                        "key": "ring_capacity",
+                        "label": "Items per Ring",
+                        "value": config.ring_capacity as f64,
+                        "min": 6.0,
+                        "max": 24.0,
+                        "step": 1.0,
+                        "unit": ""
+                    },
                     {
                         "type": "slider",
                         "key": "hover_offset",
@@ -166,6 +253,31 @@ pub fn describe() {
                         "unit": ""
                     }
                 ]
+            },
+            {
+                "title": "Dwell",
+                "items": [
+                    {
+                        "type": "slider",
+                        "key": "dwell_ms",
+                        "label": "Dwell Time (0 = off)",
+                        "value": config.dwell_ms as f64,
+                        "min": 0.0,
+                        "max": 2000.0,
+                        "step": 100.0,
+                        "unit": "ms"
+                    },
+                    {
+                        "type": "select",
+                        "key": "dwell_action",
+                        "label": "When Dwell Completes",
+                        "value": dwell_action_to_str(config.dwell_action),
+                        "options": [
+                            {"value": "Activate", "label": "Activate"},
+                            {"value": "Actions", "label": "Show Actions"}
+                        ]
+                    }
+                ]
             }
         ],
         "actions": [
@@ -214,6 +326,18 @@ pub fn set(key: &str, value: &str) {
                 config.middle_click_trigger = v;
                 Ok("Updated middle click trigger")
             }
+            "marking_menu" => {
+                let v: bool = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid boolean: {e}"))?;
+                config.marking_menu = v;
+                Ok("Updated marking menu")
+            }
+            "touchpad_select" => {
+                let v: bool = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid boolean: {e}"))?;
+                config.touchpad_select = v;
+                Ok("Updated touchpad selection")
+            }
             "swipe_up" => {
                 let v: String = serde_json::from_str(value)
                     .map_err(|e| format!("Invalid value: {e}"))?;
@@ -262,6 +386,24 @@ pub fn set(key: &str, value: &str) {
                 config.icon_spacing = v as f32;
                 Ok("Updated icon spacing")
             }
+            "fan_at_edges" => {
+                let v: bool = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid boolean: {e}"))?;
+                config.fan_at_edges = v;
+                Ok("Updated fan at edges")
+            }
+            "max_rings" => {
+                let v: f64 = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid number: {e}"))?;
+                config.max_rings = (v as usize).clamp(1, 3);
+                Ok("Updated rings")
+            }
+            "ring_capacity" => {
+                let v: f64 = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid number: {e}"))?;
+                config.ring_capacity = (v as usize).clamp(6, 24);
+                Ok("Updated items per ring")
+            }
             "hover_offset" => {
                 let v: f64 = serde_json::from_str(value)
                     .map_err(|e| format!("Invalid number: {e}"))?;
@@ -274,6 +416,59 @@ pub fn set(key: &str, value: &str) {
                 config.animation_speed = v as f32;
                 Ok("Updated animation speed")
             }
+            "smart_order" => {
+                let v: bool = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid boolean: {e}"))?;
+                config.smart_order = v;
+                Ok("Updated smart ordering")
+            }
+            "suggested_count" => {
+                let v: f64 = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid number: {e}"))?;
+                config.suggested_count = v as usize;
+                Ok("Updated suggested apps")
+            }
+            "running_placement" => {
+                let v: String = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid value: {e}"))?;
+                config.running_placement = match v.as_str() {
+                    "FirstSeen" => RunningPlacement::FirstSeen,
+                    "Alphabetical" => RunningPlacement::Alphabetical,
+                    "Separate" => RunningPlacement::Separate,
+                    _ => return Err(format!("Unknown running app placement: {v}")),
+                };
+                Ok("Updated running app placement")
+            }
+            "dwell_ms" => {
+                let v: f64 = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid number: {e}"))?;
+                config.dwell_ms = v as u64;
+                Ok("Updated dwell time")
+            }
+            "dwell_action" => {
+                let v: String = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid value: {e}"))?;
+                config.dwell_action = match v.as_str() {
+                    "Activate" => DwellAction::Activate,
+                    "Actions" => DwellAction::Actions,
+                    _ => return Err(format!("Unknown dwell action: {v}")),
+                };
+                Ok("Updated dwell action")
+            }
+            "tap_menu" | "swipe_menu" | "middle_click_menu" | "applet_menu" => {
+                let v: String = serde_json::from_str(value)
+                    .map_err(|e| format!("Invalid value: {e}"))?;
+                if v.trim().is_empty() {
+                    return Err("Menu name can't be empty".to_string());
+                }
+                match key {
+                    "tap_menu" => config.tap_menu = v,
+                    "swipe_menu" => config.swipe_menu = v,
+                    "middle_click_menu" => config.middle_click_menu = v,
+                    _ => config.applet_menu = v,
+                }
+                Ok("Updated menu")
+            }
             _ => Err(format!("Unknown key: {key}")),
         }
     })();
@@ -300,17 +495,53 @@ pub fn action(id: &str) {
     }
 }
 
-fn swipe_to_str(action: SwipeAction) -> &'static str {
+/// Options for a swipe select: the built-in actions, plus the current
+/// custom action (if any) so its value still shows
+fn swipe_options(current: &SwipeAction) -> serde_json::Value {
+    let mut options = vec![
+        serde_json::json!({"value": "None", "label": "None (System Default)"}),
+        serde_json::json!({"value": "AppLibrary", "label": "App Library"}),
+        serde_json::json!({"value": "Launcher", "label": "Launcher"}),
+        serde_json::json!({"value": "Workspaces", "label": "Workspaces"}),
+        serde_json::json!({"value": "PieMenu", "label": "Pie Menu"}),
+    ];
+    if let Some(target) = current.target() {
+        options.push(serde_json::json!({
+            "value": swipe_to_str(current),
+            "label": format!("Custom: {target}")
+        }));
+    }
+    serde_json::Value::Array(options)
+}
+
+/// Custom actions are written as `Command:<program> [args...]`,
+/// `OpenUrl:<url>` or `Desktop:<app id>`
+fn swipe_to_str(action: &SwipeAction) -> String {
     match action {
-        SwipeAction::None => "None",
-        SwipeAction::AppLibrary => "AppLibrary",
-        SwipeAction::Launcher => "Launcher",
-        SwipeAction::Workspaces => "Workspaces",
-        SwipeAction::PieMenu => "PieMenu",
+        SwipeAction::None => "None".to_string(),
+        SwipeAction::AppLibrary => "AppLibrary".to_string(),
+        SwipeAction::Launcher => "Launcher".to_string(),
+        SwipeAction::Workspaces => "Workspaces".to_string(),
+        SwipeAction::PieMenu => "PieMenu".to_string(),
+        SwipeAction::Command { .. } => format!("Command:{}", action.target().unwrap_or_default()),
+        SwipeAction::OpenUrl(url) => format!("OpenUrl:{url}"),
+        SwipeAction::Desktop(app_id) => format!("Desktop:{app_id}"),
     }
 }
 
 fn str_to_swipe(s: &str) -> Result<SwipeAction, String> {
+    if let Some((kind, target)) = s.split_once(':') {
+        let template = match kind {
+            "Command" => SwipeAction::Command { exec: String::new(), args: Vec::new() },
+            "OpenUrl" => SwipeAction::OpenUrl(String::new()),
+            "Desktop" => SwipeAction::Desktop(String::new()),
+            _ => return Err(format!("Unknown swipe action: {s}")),
+        };
+        if target.trim().is_empty() {
+            return Err(format!("{kind} needs a target, e.g. {kind}:..."));
+        }
+        return Ok(template.with_target(target));
+    }
     match s {
         "None" => Ok(SwipeAction::None),
         "AppLibrary" => Ok(SwipeAction::AppLibrary),
@@ -321,6 +552,21 @@ fn str_to_swipe(s: &str) -> Result<SwipeAction, String> {
     }
 }
 
+fn running_placement_to_str(placement: RunningPlacement) -> &'static str {
+    match placement {
+        RunningPlacement::FirstSeen => "FirstSeen",
+        RunningPlacement::Alphabetical => "Alphabetical",
+        RunningPlacement::Separate => "Separate",
+    }
+}
+
+fn dwell_action_to_str(action: DwellAction) -> &'static str {
+    match action {
+        DwellAction::Activate => "Activate",
+        DwellAction::Actions => "Actions",
+    }
+}
+
 fn print_response(ok: bool, message: &str) {
     let resp = serde_json::json!({"ok": ok, "message": message});
     println!("{}", resp);
@@ -8,32 +8,77 @@ use cosmic::iced::Length;
 use cosmic::widget::{self, settings, text, dropdown};
 use cosmic::Element;
 
-use crate::config::{PieMenuConfig, SwipeAction, WorkspaceLayout, read_workspace_layout};
+use crate::config::{DwellAction, PieMenuConfig, RunningPlacement, SwipeAction, WorkspaceLayout, read_workspace_layout};
+use crate::menus;
 
 const FINGER_OPTIONS: &[&str] = &["3 fingers", "4 fingers"];
 
+const DWELL_ACTION_OPTIONS: &[&str] = &["Activate", "Show Actions"];
+
+/// Dwell actions in the order of `DWELL_ACTION_OPTIONS`
+const DWELL_ACTIONS: &[DwellAction] = &[DwellAction::Activate, DwellAction::Actions];
+
+const RUNNING_PLACEMENT_OPTIONS: &[&str] = &["In Order Opened", "By Name", "Own Section"];
+
+/// Placements in the order of `RUNNING_PLACEMENT_OPTIONS`
+const RUNNING_PLACEMENTS: &[RunningPlacement] = &[
+    RunningPlacement::FirstSeen,
+    RunningPlacement::Alphabetical,
+    RunningPlacement::Separate,
+];
+
 const SWIPE_ACTION_OPTIONS: &[&str] = &[
     "None (system default)",
     "App Library",
     "Launcher",
     "Workspaces",
     "Pie Menu",
+    "Run Command",
+    "Open URL",
+    "Launch App",
 ];
 
-fn swipe_action_to_index(action: SwipeAction) -> usize {
+fn swipe_action_to_index(action: &SwipeAction) -> usize {
     SwipeAction::all()
         .iter()
-        .position(|&a| a == action)
+        .position(|a| a.same_kind(action))
         .unwrap_or(0)
 }
 
+fn menu_position(names: &[String], name: &str) -> Option<usize> {
+    names.iter().position(|n| n == name)
+}
+
 fn index_to_swipe_action(index: usize) -> SwipeAction {
     SwipeAction::all()
         .get(index)
-        .copied()
+        .cloned()
         .unwrap_or_default()
 }
 
+/// Text field for the target of a custom swipe action, if it has one
+fn swipe_target_item<'a>(
+    action: &SwipeAction,
+    target: &'a str,
+    on_input: fn(String) -> Message,
+) -> Option<Element<'a, Message>> {
+    let (label, placeholder) = match action {
+        SwipeAction::Command { .. } => ("Command", "program --arg"),
+        SwipeAction::OpenUrl(_) => ("URL", "https://..."),
+        SwipeAction::Desktop(_) => ("App ID", "org.mozilla.firefox"),
+        _ => return None,
+    };
+    Some(
+        settings::item(
+            label,
+            widget::text_input(placeholder, target)
+                .on_input(on_input)
+                .width(Length::Fixed(200.0)),
+        )
+        .into(),
+    )
+}
+
 pub struct State {
     pub config: PieMenuConfig,
     pub finger_index: usize,
@@ -41,7 +86,14 @@ pub struct State {
     pub swipe_down_index: usize,
     pub swipe_left_index: usize,
     pub swipe_right_index: usize,
+    /// Text of the target fields for custom swipe actions
+    pub swipe_up_target: String,
+    pub swipe_down_target: String,
+    pub swipe_left_target: String,
+    pub swipe_right_target: String,
     pub workspace_layout: WorkspaceLayout,
+    /// Menus the trigger dropdowns offer
+    pub menu_names: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -54,9 +106,27 @@ pub enum Message {
     SwipeDownChanged(usize),
     SwipeLeftChanged(usize),
     SwipeRightChanged(usize),
+    SwipeUpTargetChanged(String),
+    SwipeDownTargetChanged(String),
+    SwipeLeftTargetChanged(String),
+    SwipeRightTargetChanged(String),
     ShowBackgroundToggled(bool),
     IconOnlyHighlightToggled(bool),
+    FanAtEdgesToggled(bool),
+    MaxRingsChanged(f32),
+    RingCapacityChanged(f32),
     MiddleClickToggled(bool),
+    MarkingMenuToggled(bool),
+    TouchpadSelectToggled(bool),
+    TapMenuChanged(usize),
+    SwipeMenuChanged(usize),
+    MiddleClickMenuChanged(usize),
+    AppletMenuChanged(usize),
+    SmartOrderToggled(bool),
+    RunningPlacementChanged(usize),
+    SuggestedCountChanged(f32),
+    DwellTimeChanged(f32),
+    DwellActionChanged(usize),
     ResetDefaults,
 }
 
@@ -64,15 +134,21 @@ pub fn init() -> State {
     let config = PieMenuConfig::load();
     let finger_index = if config.finger_count == 3 { 0 } else { 1 };
     let workspace_layout = read_workspace_layout();
+    let menu_names = menus::menu_choices(&config);
 
     State {
         finger_index,
-        swipe_up_index: swipe_action_to_index(config.swipe_up),
-        swipe_down_index: swipe_action_to_index(config.swipe_down),
-        swipe_left_index: swipe_action_to_index(config.swipe_left),
-        swipe_right_index: swipe_action_to_index(config.swipe_right),
+        swipe_up_index: swipe_action_to_index(&config.swipe_up),
+        swipe_down_index: swipe_action_to_index(&config.swipe_down),
+        swipe_left_index: swipe_action_to_index(&config.swipe_left),
+        swipe_right_index: swipe_action_to_index(&config.swipe_right),
+        swipe_up_target: config.swipe_up.target().unwrap_or_default(),
+        swipe_down_target: config.swipe_down.target().unwrap_or_default(),
+        swipe_left_target: config.swipe_left.target().unwrap_or_default(),
+        swipe_right_target: config.swipe_right.target().unwrap_or_default(),
         config,
         workspace_layout,
+        menu_names,
     }
 }
 
@@ -97,22 +173,42 @@ pub fn update(state: &mut State, message: Message) {
         }
         Message::SwipeUpChanged(index) => {
             state.swipe_up_index = index;
-            state.config.swipe_up = index_to_swipe_action(index);
+            state.config.swipe_up = index_to_swipe_action(index).with_target(&state.swipe_up_target);
+            let _ = state.config.save();
+        }
+        Message::SwipeUpTargetChanged(target) => {
+            state.config.swipe_up = state.config.swipe_up.with_target(&target);
+            state.swipe_up_target = target;
             let _ = state.config.save();
         }
         Message::SwipeDownChanged(index) => {
             state.swipe_down_index = index;
-            state.config.swipe_down = index_to_swipe_action(index);
+            state.config.swipe_down = index_to_swipe_action(index).with_target(&state.swipe_down_target);
+            let _ = state.config.save();
+        }
+        Message::SwipeDownTargetChanged(target) => {
+            state.config.swipe_down = state.config.swipe_down.with_target(&target);
+            state.swipe_down_target = target;
             let _ = state.config.save();
         }
         Message::SwipeLeftChanged(index) => {
             state.swipe_left_index = index;
-            state.config.swipe_left = index_to_swipe_action(index);
+            state.config.swipe_left = index_to_swipe_action(index).with_target(&state.swipe_left_target);
+            let _ = state.config.save();
+        }
+        Message::SwipeLeftTargetChanged(target) => {
+            state.config.swipe_left = state.config.swipe_left.with_target(&target);
+            state.swipe_left_target = target;
             let _ = state.config.save();
         }
         Message::SwipeRightChanged(index) => {
             state.swipe_right_index = index;
-            state.config.swipe_right = index_to_swipe_action(index);
+            state.config.swipe_right = index_to_swipe_action(index).with_target(&state.swipe_right_target);
+            let _ = state.config.save();
+        }
+        Message::SwipeRightTargetChanged(target) => {
+            state.config.swipe_right = state.config.swipe_right.with_target(&target);
+            state.swipe_right_target = target;
             let _ = state.config.save();
         }
         Message::ShowBackgroundToggled(enabled) => {
@@ -123,17 +219,86 @@ pub fn update(state: &mut State, message: Message) {
             state.config.icon_only_highlight = enabled;
             let _ = state.config.save();
         }
+        Message::FanAtEdgesToggled(enabled) => {
+            state.config.fan_at_edges = enabled;
+            let _ = state.config.save();
+        }
+        Message::MaxRingsChanged(value) => {
+            state.config.max_rings = value as usize;
+            let _ = state.config.save();
+        }
+        Message::RingCapacityChanged(value) => {
+            state.config.ring_capacity = value as usize;
+            let _ = state.config.save();
+        }
         Message::MiddleClickToggled(enabled) => {
             state.config.middle_click_trigger = enabled;
             let _ = state.config.save();
         }
+        Message::MarkingMenuToggled(enabled) => {
+            state.config.marking_menu = enabled;
+            let _ = state.config.save();
+        }
+        Message::TouchpadSelectToggled(enabled) => {
+            state.config.touchpad_select = enabled;
+            let _ = state.config.save();
+        }
+        Message::TapMenuChanged(index) => {
+            if let Some(name) = state.menu_names.get(index) {
+                state.config.tap_menu = name.clone();
+                let _ = state.config.save();
+            }
+        }
+        Message::SwipeMenuChanged(index) => {
+            if let Some(name) = state.menu_names.get(index) {
+                state.config.swipe_menu = name.clone();
+                let _ = state.config.save();
+            }
+        }
+        Message::MiddleClickMenuChanged(index) => {
+            if let Some(name) = state.menu_names.get(index) {
+                state.config.middle_click_menu = name.clone();
+                let _ = state.config.save();
+            }
+        }
+        Message::AppletMenuChanged(index) => {
+            if let Some(name) = state.menu_names.get(index) {
+                state.config.applet_menu = name.clone();
+                let _ = state.config.save();
+            }
+        }
+        Message::SmartOrderToggled(enabled) => {
+            state.config.smart_order = enabled;
+            let _ = state.config.save();
+        }
+        Message::RunningPlacementChanged(index) => {
+            state.config.running_placement = RUNNING_PLACEMENTS.get(index).copied().unwrap_or_default();
+            let _ = state.config.save();
+        }
+        Message::SuggestedCountChanged(value) => {
+            state.config.suggested_count = value as usize;
+            let _ = state.config.save();
+        }
+        Message::DwellTimeChanged(value) => {
+            state.config.dwell_ms = value as u64;
+            let _ = state.config.save();
+        }
+        Message::DwellActionChanged(index) => {
+            state.config.dwell_action = DWELL_ACTIONS.get(index).copied().unwrap_or_default();
+            let _ = state.config.save();
+        }
         Message::ResetDefaults => {
             state.config = PieMenuConfig::default();
             state.finger_index = if state.config.finger_count == 3 { 0 } else { 1 };
-            state.swipe_up_index = swipe_action_to_index(state.config.swipe_up);
-            state.swipe_down_index = swipe_action_to_index(state.config.swipe_down);
-            state.swipe_left_index = swipe_action_to_index(state.config.swipe_left);
-            state.swipe_right_index = swipe_action_to_index(state.config.swipe_right);
+            state.swipe_up_index = swipe_action_to_index(&state.config.swipe_up);
+            state.swipe_down_index = swipe_action_to_index(&state.config.swipe_down);
+            state.swipe_left_index = swipe_action_to_index(&state.config.swipe_left);
+            state.swipe_right_index = swipe_action_to_index(&state.config.swipe_right);
+            state.swipe_up_target.clear();
+            state.swipe_down_target.clear();
+            state.swipe_left_target.clear();
+            state.swipe_right_target.clear();
+            state.menu_names = menus::menu_choices(&state.config);
             let _ = state.config.save();
         }
     }
@@ -197,6 +362,20 @@ pub fn view(state: &State) -> Element<'_, Message> {
                 widget::toggler(state.config.middle_click_trigger)
                     .on_toggle(Message::MiddleClickToggled),
             )
+        )
+        .add(
+            settings::item(
+                "Hold, Move and Release to Select",
+                widget::toggler(state.config.marking_menu)
+                    .on_toggle(Message::MarkingMenuToggled),
+            )
+        )
+        .add(
+            settings::item(
+                "Drag on the Touchpad After the Tap to Select",
+                widget::toggler(state.config.touchpad_select)
+                    .on_toggle(Message::TouchpadSelectToggled),
+            )
         );
 
     let (layout_name, available_directions) = match state.workspace_layout {
@@ -220,7 +399,11 @@ pub fn view(state: &State) -> Element<'_, Message> {
                         )
                         .width(Length::Fixed(200.0)),
                     )
-                )
+                );
+            if let Some(item) = swipe_target_item(&state.config.swipe_up, &state.swipe_up_target, Message::SwipeUpTargetChanged) {
+                swipe_section = swipe_section.add(item);
+            }
+            swipe_section = swipe_section
                 .add(
                     settings::item(
                         "Swipe Down",
@@ -232,6 +415,9 @@ pub fn view(state: &State) -> Element<'_, Message> {
                         .width(Length::Fixed(200.0)),
                     )
                 );
+            if let Some(item) = swipe_target_item(&state.config.swipe_down, &state.swipe_down_target, Message::SwipeDownTargetChanged) {
+                swipe_section = swipe_section.add(item);
+            }
         }
         WorkspaceLayout::Vertical => {
             swipe_section = swipe_section
@@ -245,7 +431,11 @@ pub fn view(state: &State) -> Element<'_, Message> {
                         )
                         .width(Length::Fixed(200.0)),
                     )
-                )
+                );
+            if let Some(item) = swipe_target_item(&state.config.swipe_left, &state.swipe_left_target, Message::SwipeLeftTargetChanged) {
+                swipe_section = swipe_section.add(item);
+            }
+            swipe_section = swipe_section
                 .add(
                     settings::item(
                         "Swipe Right",
@@ -257,6 +447,9 @@ pub fn view(state: &State) -> Element<'_, Message> {
                         .width(Length::Fixed(200.0)),
                     )
                 );
+            if let Some(item) = swipe_target_item(&state.config.swipe_right, &state.swipe_right_target, Message::SwipeRightTargetChanged) {
+                swipe_section = swipe_section.add(item);
+            }
         }
     }
 
@@ -279,6 +472,89 @@ pub fn view(state: &State) -> Element<'_, Message> {
         )
     );
 
+    let menus_section = settings::section()
+        .title("Menus")
+        .add(
+            settings::item(
+                "Tap Gesture",
+                dropdown(
+                    &state.menu_names,
+                    menu_position(&state.menu_names, &state.config.tap_menu),
+                    Message::TapMenuChanged,
+                )
+                .width(Length::Fixed(200.0)),
+            )
+        )
+        .add(
+            settings::item(
+                "Swipe Gesture",
+                dropdown(
+                    &state.menu_names,
+                    menu_position(&state.menu_names, &state.config.swipe_menu),
+                    Message::SwipeMenuChanged,
+                )
+                .width(Length::Fixed(200.0)),
+            )
+        )
+        .add(
+            settings::item(
+                "Middle Mouse Click",
+                dropdown(
+                    &state.menu_names,
+                    menu_position(&state.menu_names, &state.config.middle_click_menu),
+                    Message::MiddleClickMenuChanged,
+                )
+                .width(Length::Fixed(200.0)),
+            )
+        )
+        .add(
+            settings::item(
+                "Panel Applet",
+                dropdown(
+                    &state.menu_names,
+                    menu_position(&state.menu_names, &state.config.applet_menu),
+                    Message::AppletMenuChanged,
+                )
+                .width(Length::Fixed(200.0)),
+            )
+        )
+        .add(
+            settings::item(
+                "Order by Use",
+                widget::toggler(state.config.smart_order)
+                    .on_toggle(Message::SmartOrderToggled),
+            )
+        )
+        .add(
+            settings::item(
+                "Running Apps",
+                dropdown(
+                    RUNNING_PLACEMENT_OPTIONS,
+                    RUNNING_PLACEMENTS.iter().position(|p| *p == state.config.running_placement),
+                    Message::RunningPlacementChanged,
+                )
+                .width(Length::Fixed(200.0)),
+            )
+        )
+        .add(
+            settings::flex_item(
+                "Suggested Apps",
+                widget::Row::new()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text::body(state.config.suggested_count.to_string()))
+                    .push(
+                        widget::slider(
+                            0.0..=8.0,
+                            state.config.suggested_count as f32,
+                            Message::SuggestedCountChanged,
+                        )
+                        .step(1.0)
+                        .width(Length::Fill)
+                    ),
+            )
+        );
+
     let appearance_section = settings::section()
         .title("Appearance")
         .add(
@@ -294,6 +570,86 @@ pub fn view(state: &State) -> Element<'_, Message> {
                 widget::toggler(state.config.icon_only_highlight)
                     .on_toggle(Message::IconOnlyHighlightToggled),
             )
+        )
+        .add(
+            settings::item(
+                "Fan Out at Screen Edges",
+                widget::toggler(state.config.fan_at_edges)
+                    .on_toggle(Message::FanAtEdgesToggled),
+            )
+        )
+        .add(
+            settings::flex_item(
+                "Rings",
+                widget::Row::new()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text::body(state.config.max_rings.to_string()))
+                    .push(
+                        widget::slider(
+                            1.0..=3.0,
+                            state.config.max_rings as f32,
+                            Message::MaxRingsChanged,
+                        )
+                        .step(1.0)
+                        .width(Length::Fill)
+                    ),
+            )
+        )
+        .add(
+            settings::flex_item(
+                "Items per Ring",
+                widget::Row::new()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text::body(state.config.ring_capacity.to_string()))
+                    .push(
+                        widget::slider(
+                            6.0..=24.0,
+                            state.config.ring_capacity as f32,
+                            Message::RingCapacityChanged,
+                        )
+                        .step(1.0)
+                        .width(Length::Fill)
+                    ),
+            )
+        );
+
+    let dwell_label = if state.config.dwell_ms == 0 {
+        String::from("Off")
+    } else {
+        format!("{}ms", state.config.dwell_ms)
+    };
+    let dwell_section = settings::section()
+        .title("Dwell")
+        .add(
+            settings::flex_item(
+                "Dwell Time",
+                widget::Row::new()
+                    .spacing(8)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(text::body(dwell_label))
+                    .push(
+                        widget::slider(
+                            0.0..=2000.0,
+                            state.config.dwell_ms as f32,
+                            Message::DwellTimeChanged,
+                        )
+                        .step(100.0)
+                        .width(Length::Fill)
+                    ),
+            )
+        )
+        .add(
+            settings::item(
+                "When Dwell Completes",
+                dropdown(
+                    DWELL_ACTION_OPTIONS,
+                    DWELL_ACTIONS.iter().position(|a| *a == state.config.dwell_action),
+                    Message::DwellActionChanged,
+                )
+                .width(Length::Fixed(150.0)),
+            )
         );
 
     let reset_button = widget::button::standard("Reset to Defaults")
@@ -308,8 +664,12 @@ pub fn view(state: &State) -> Element<'_, Message> {
             layout_name, available_directions
         )).into(),
         swipe_section.into(),
+        text::caption("Choose which menu from menu.json each trigger opens, and how the dock mirror is ordered.").into(),
+        menus_section.into(),
         text::caption("Customize the visual appearance of the pie menu.").into(),
         appearance_section.into(),
+        text::caption("Rest the pointer on a slice to choose it without clicking.").into(),
+        dwell_section.into(),
         widget::container(reset_button)
             .padding([16, 0, 0, 0])
             .into(),
@@ -1,14 +1,15 @@
 //! Running Windows Detection Module
 //!
 //! Uses the ext-foreign-toplevel-list Wayland protocol to detect
-//! which applications currently have open windows.
+//! which applications currently have open windows, and their titles.
 //!
-//! Also provides window activation using zcosmic_toplevel_manager_v1.
+//! Also provides window management (activate, close, minimize, maximize,
+//! fullscreen, move to workspace) using zcosmic_toplevel_manager_v1.
 
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap};
 use wayland_client::{
     Connection, Dispatch, QueueHandle, Proxy,
+    protocol::wl_output::{self, WlOutput},
     protocol::wl_registry::{self, WlRegistry},
     protocol::wl_seat::{self, WlSeat},
 };
@@ -23,22 +24,38 @@ use cosmic_protocols::toplevel_info::v1::client::{
 use cosmic_protocols::toplevel_management::v1::client::{
     zcosmic_toplevel_manager_v1::{self, ZcosmicToplevelManagerV1},
 };
+use cosmic_protocols::workspace::v1::client::{
+    zcosmic_workspace_manager_v1::{self, ZcosmicWorkspaceManagerV1},
+    zcosmic_workspace_group_handle_v1::{self, ZcosmicWorkspaceGroupHandleV1},
+    zcosmic_workspace_handle_v1::{self, ZcosmicWorkspaceHandleV1},
+};
+
+/// An open window
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowInfo {
+    /// Stable identifier from ext_foreign_toplevel_handle_v1, unique per window
+    pub identifier: String,
+    /// App ID reported by the window
+    pub app_id: String,
+    /// Window title
+    pub title: String,
+}
 
 /// State for tracking running windows
 struct ToplevelState {
-    /// Map of app_ids to window count for currently running applications
-    running_apps: Arc<Mutex<HashMap<String, u32>>>,
-    /// Current app_id being built for a handle
-    pending_app_ids: std::collections::HashMap<u32, String>,
+    /// Open windows, keyed by handle protocol ID (so they stay in creation order)
+    windows: BTreeMap<u32, WindowInfo>,
+    /// Window details being built for a handle until its done event
+    pending: HashMap<u32, WindowInfo>,
     /// Whether the foreign toplevel list was found
     manager_bound: bool,
 }
 
 impl ToplevelState {
-    fn new(running_apps: Arc<Mutex<HashMap<String, u32>>>) -> Self {
+    fn new() -> Self {
         Self {
-            running_apps,
-            pending_app_ids: std::collections::HashMap::new(),
+            windows: BTreeMap::new(),
+            pending: HashMap::new(),
             manager_bound: false,
         }
     }
@@ -101,28 +118,25 @@ impl Dispatch<ExtForeignToplevelHandleV1, ()> for ToplevelState {
         let handle_id = handle.id().protocol_id();
 
         match event {
+            ext_foreign_toplevel_handle_v1::Event::Identifier { identifier } => {
+                state.pending.entry(handle_id).or_default().identifier = identifier;
+            }
             ext_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
-                state.pending_app_ids.insert(handle_id, app_id);
+                state.pending.entry(handle_id).or_default().app_id = app_id;
+            }
+            ext_foreign_toplevel_handle_v1::Event::Title { title } => {
+                state.pending.entry(handle_id).or_default().title = title;
             }
-            ext_foreign_toplevel_handle_v1::Event::Title { .. } => {}
             ext_foreign_toplevel_handle_v1::Event::Done => {
-                if let Some(app_id) = state.pending_app_ids.get(&handle_id) {
-                    if let Ok(mut running) = state.running_apps.lock() {
-                        *running.entry(app_id.clone()).or_insert(0) += 1;
+                if let Some(window) = state.pending.get(&handle_id) {
+                    if !window.app_id.is_empty() {
+                        state.windows.insert(handle_id, window.clone());
                     }
                 }
             }
             ext_foreign_toplevel_handle_v1::Event::Closed => {
-                if let Some(app_id) = state.pending_app_ids.remove(&handle_id) {
-                    if let Ok(mut running) = state.running_apps.lock() {
-                        if let Some(count) = running.get_mut(&app_id) {
-                            *count = count.saturating_sub(1);
-                            if *count == 0 {
-                                running.remove(&app_id);
-                            }
-                        }
-                    }
-                }
+                state.pending.remove(&handle_id);
+                state.windows.remove(&handle_id);
             }
             _ => {}
         }
@@ -131,8 +145,17 @@ impl Dispatch<ExtForeignToplevelHandleV1, ()> for ToplevelState {
 
 /// Get a snapshot of currently running application IDs with window counts
 pub fn get_running_apps() -> HashMap<String, u32> {
+    let mut running = HashMap::new();
+    for window in get_windows() {
+        *running.entry(window.app_id).or_insert(0) += 1;
+    }
+    running
+}
+
+/// Get a snapshot of all open windows, oldest first
+pub fn get_windows() -> Vec<WindowInfo> {
     // Run the Wayland query in a separate scope to ensure cleanup
-    let result = query_running_apps();
+    let result = query_windows();
 
     // Small delay to ensure Wayland resources are fully released
     std::thread::sleep(std::time::Duration::from_millis(50));
@@ -140,32 +163,30 @@ pub fn get_running_apps() -> HashMap<String, u32> {
     result
 }
 
-fn query_running_apps() -> HashMap<String, u32> {
-    let running_apps = Arc::new(Mutex::new(HashMap::new()));
-
+fn query_windows() -> Vec<WindowInfo> {
     // Try to connect to Wayland
     let conn = match Connection::connect_to_env() {
         Ok(c) => c,
-        Err(_) => return HashMap::new(),
+        Err(_) => return Vec::new(),
     };
 
     let display = conn.display();
     let mut event_queue = conn.new_event_queue();
     let qh = event_queue.handle();
 
-    let mut state = ToplevelState::new(running_apps.clone());
+    let mut state = ToplevelState::new();
 
     // Get the registry
     let _registry = display.get_registry(&qh, ());
 
     // Roundtrip to get globals
     if event_queue.roundtrip(&mut state).is_err() {
-        return HashMap::new();
+        return Vec::new();
     }
 
     // Another roundtrip to get toplevel info
     if event_queue.roundtrip(&mut state).is_err() {
-        return HashMap::new();
+        return Vec::new();
     }
 
     // One more to ensure all Done events are received
@@ -175,44 +196,191 @@ fn query_running_apps() -> HashMap<String, u32> {
     drop(event_queue);
     drop(conn);
 
-    // Return the collected app IDs with counts
-    match Arc::try_unwrap(running_apps) {
-        Ok(mutex) => mutex.into_inner().unwrap_or_default(),
-        Err(arc) => arc.lock().unwrap().clone(),
+    state.windows.into_values().collect()
+}
+
+/// Query open windows via subprocess to avoid Wayland connection conflicts
+pub fn query_windows_via_subprocess() -> Vec<WindowInfo> {
+    let exe = std::env::current_exe().unwrap_or_else(|_| "cosmic-pie-menu".into());
+    match std::process::Command::new(&exe).arg("--query-windows").output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_window_line)
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to query windows: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Format a window as an "identifier<TAB>app_id<TAB>title" line for
+/// `--query-windows`
+///
+/// Tabs and newlines in the title become spaces. The title is only shown,
+/// windows are acted on by identifier.
+pub fn format_window_line(window: &WindowInfo) -> String {
+    let title: String = window
+        .title
+        .chars()
+        .map(|c| if c == '\t' || c == '\n' { ' ' } else { c })
+        .collect();
+    format!("{}\t{}\t{}", window.identifier, window.app_id, title)
+}
+
+/// Parse a line written by [`format_window_line`]
+fn parse_window_line(line: &str) -> Option<WindowInfo> {
+    let mut fields = line.splitn(3, '\t');
+    let identifier = fields.next()?;
+    let app_id = fields.next()?;
+    let title = fields.next()?;
+    if app_id.is_empty() {
+        return None;
     }
+    Some(WindowInfo {
+        identifier: identifier.to_string(),
+        app_id: app_id.to_string(),
+        title: title.to_string(),
+    })
 }
 
 // ============================================================================
-// Window Activation
+// Window Management
 // ============================================================================
 
-/// State for window activation
-struct ActivationState {
-    /// Target app_id to activate
+/// An operation on a window through zcosmic_toplevel_manager_v1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAction {
+    /// Focus the window
+    Activate,
+    /// Ask the window to close
+    Close,
+    /// Minimize the window
+    Minimize,
+    /// Maximize the window, or restore it if already maximized
+    ToggleMaximize,
+    /// Make the window fullscreen, or leave fullscreen
+    ToggleFullscreen,
+    /// Move the window to a workspace, by index into [`get_workspaces`]
+    MoveToWorkspace(usize),
+}
+
+/// Toplevel states reported by zcosmic_toplevel_handle_v1
+const STATE_MAXIMIZED: u32 = 0;
+const STATE_FULLSCREEN: u32 = 3;
+
+/// A workspace group (one per output) and its workspaces, in creation order
+struct WorkspaceGroup {
+    /// Protocol ID of the group handle
+    id: u32,
+    /// Output the group is shown on
+    output: Option<WlOutput>,
+    /// Workspaces in the group
+    workspaces: Vec<ZcosmicWorkspaceHandleV1>,
+}
+
+/// State for window management (activation, close, minimize, ...)
+struct ManagementState {
+    /// Target app_id to act on
     target_app_id: String,
-    /// Found toplevel handle matching the app_id (COSMIC handle)
+    /// Target window identifier (the oldest window of the app if None)
+    target_identifier: Option<String>,
+    /// Found toplevel handle matching the target (COSMIC handle)
     found_handle: Option<ZcosmicToplevelHandleV1>,
+    /// The COSMIC toplevel info global
+    info: Option<ZcosmicToplevelInfoV1>,
+    /// Toplevels from the ext foreign toplevel list with their app_id and
+    /// identifier, keyed by handle protocol ID (so they stay in creation order)
+    foreign: BTreeMap<u32, (ExtForeignToplevelHandleV1, WindowInfo)>,
     /// The COSMIC toplevel manager
     manager: Option<ZcosmicToplevelManagerV1>,
     /// The seat (for activation request)
     seat: Option<WlSeat>,
     /// Current app_id being built for a handle (keyed by protocol ID)
     pending_app_ids: std::collections::HashMap<u32, String>,
+    /// Current states of each handle (keyed by protocol ID)
+    states: std::collections::HashMap<u32, Vec<u32>>,
+    /// Workspace groups, in creation order
+    workspace_groups: Vec<WorkspaceGroup>,
+    /// Workspace names (keyed by workspace handle protocol ID)
+    workspace_names: std::collections::HashMap<u32, String>,
 }
 
-impl ActivationState {
-    fn new(target_app_id: String) -> Self {
+impl ManagementState {
+    fn new(target_app_id: String, target_identifier: Option<String>) -> Self {
         Self {
             target_app_id,
+            target_identifier,
             found_handle: None,
+            info: None,
+            foreign: BTreeMap::new(),
             manager: None,
             seat: None,
             pending_app_ids: std::collections::HashMap::new(),
+            states: std::collections::HashMap::new(),
+            workspace_groups: Vec::new(),
+            workspace_names: std::collections::HashMap::new(),
+        }
+    }
+
+    /// The target window in the ext foreign toplevel list
+    fn find_foreign(&self) -> Option<ExtForeignToplevelHandleV1> {
+        if self.target_app_id.is_empty() {
+            return None;
         }
+        self.foreign
+            .values()
+            .find(|(_, window)| match &self.target_identifier {
+                Some(identifier) => &window.identifier == identifier,
+                None => window.app_id.eq_ignore_ascii_case(&self.target_app_id),
+            })
+            .map(|(handle, _)| handle.clone())
+    }
+
+    /// Whether the found window currently has a state
+    fn found_has_state(&self, wanted: u32) -> bool {
+        self.found_handle
+            .as_ref()
+            .and_then(|h| self.states.get(&h.id().protocol_id()))
+            .is_some_and(|states| states.contains(&wanted))
+    }
+
+    /// All workspaces across groups, with the output of their group
+    fn workspaces(&self) -> Vec<(&ZcosmicWorkspaceHandleV1, Option<&WlOutput>)> {
+        self.workspace_groups
+            .iter()
+            .flat_map(|group| group.workspaces.iter().map(move |ws| (ws, group.output.as_ref())))
+            .collect()
+    }
+
+    /// Display names for [`ManagementState::workspaces`]
+    ///
+    /// With several outputs, names repeat per output, so they're numbered by group.
+    fn workspace_labels(&self) -> Vec<String> {
+        let multiple_groups = self.workspace_groups.len() > 1;
+        self.workspace_groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group_index, group)| {
+                group.workspaces.iter().enumerate().map(move |(i, ws)| (group_index, i, ws))
+            })
+            .map(|(group_index, i, ws)| {
+                let name = self
+                    .workspace_names
+                    .get(&ws.id().protocol_id())
+                    .cloned()
+                    .unwrap_or_else(|| (i + 1).to_string());
+                if multiple_groups {
+                    format!("Workspace {} (display {})", name, group_index + 1)
+                } else {
+                    format!("Workspace {}", name)
+                }
+            })
+            .collect()
     }
 }
 
-impl Dispatch<WlRegistry, ()> for ActivationState {
+impl Dispatch<WlRegistry, ()> for ManagementState {
     fn event(
         state: &mut Self,
         registry: &WlRegistry,
@@ -224,7 +392,16 @@ impl Dispatch<WlRegistry, ()> for ActivationState {
         if let wl_registry::Event::Global { name, interface, version } = event {
             match interface.as_str() {
                 "zcosmic_toplevel_info_v1" => {
-                    registry.bind::<ZcosmicToplevelInfoV1, _, _>(
+                    // Version 2 maps ext foreign toplevels to COSMIC handles
+                    state.info = Some(registry.bind::<ZcosmicToplevelInfoV1, _, _>(
+                        name,
+                        version.min(2),
+                        qh,
+                        (),
+                    ));
+                }
+                "ext_foreign_toplevel_list_v1" => {
+                    registry.bind::<ExtForeignToplevelListV1, _, _>(
                         name,
                         version.min(1),
                         qh,
@@ -239,6 +416,18 @@ impl Dispatch<WlRegistry, ()> for ActivationState {
                         (),
                     ));
                 }
+                "zcosmic_workspace_manager_v1" => {
+                    registry.bind::<ZcosmicWorkspaceManagerV1, _, _>(
+                        name,
+                        version.min(1),
+                        qh,
+                        (),
+                    );
+                }
+                "wl_output" => {
+                    // Bound so workspace groups can report which output they're on
+                    registry.bind::<WlOutput, _, _>(name, version.min(1), qh, ());
+                }
                 "wl_seat" => {
                     if state.seat.is_none() {
                         state.seat = Some(registry.bind::<WlSeat, _, _>(
@@ -255,7 +444,7 @@ impl Dispatch<WlRegistry, ()> for ActivationState {
     }
 }
 
-impl Dispatch<ZcosmicToplevelInfoV1, ()> for ActivationState {
+impl Dispatch<ZcosmicToplevelInfoV1, ()> for ManagementState {
     fn event(
         _state: &mut Self,
         _info: &ZcosmicToplevelInfoV1,
@@ -267,12 +456,12 @@ impl Dispatch<ZcosmicToplevelInfoV1, ()> for ActivationState {
         // Events are handled in the handle dispatch
     }
 
-    wayland_client::event_created_child!(ActivationState, ZcosmicToplevelInfoV1, [
+    wayland_client::event_created_child!(ManagementState, ZcosmicToplevelInfoV1, [
         zcosmic_toplevel_info_v1::EVT_TOPLEVEL_OPCODE => (ZcosmicToplevelHandleV1, ()),
     ]);
 }
 
-impl Dispatch<ZcosmicToplevelHandleV1, ()> for ActivationState {
+impl Dispatch<ZcosmicToplevelHandleV1, ()> for ManagementState {
     fn event(
         state: &mut Self,
         handle: &ZcosmicToplevelHandleV1,
@@ -287,10 +476,21 @@ impl Dispatch<ZcosmicToplevelHandleV1, ()> for ActivationState {
             zcosmic_toplevel_handle_v1::Event::AppId { app_id } => {
                 state.pending_app_ids.insert(handle_id, app_id);
             }
+            zcosmic_toplevel_handle_v1::Event::State { state: raw } => {
+                // Array of native-endian u32 state values
+                let states = raw
+                    .chunks_exact(4)
+                    .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                state.states.insert(handle_id, states);
+            }
             zcosmic_toplevel_handle_v1::Event::Done => {
+                // Version 1 of the info protocol has no identifiers, so only
+                // "any window of the app" can be matched here
                 if let Some(app_id) = state.pending_app_ids.get(&handle_id) {
                     // Check if this matches our target (case-insensitive for flexibility)
                     if app_id.eq_ignore_ascii_case(&state.target_app_id)
+                        && state.target_identifier.is_none()
                         && state.found_handle.is_none()
                     {
                         state.found_handle = Some(handle.clone());
@@ -302,7 +502,54 @@ impl Dispatch<ZcosmicToplevelHandleV1, ()> for ActivationState {
     }
 }
 
-impl Dispatch<ZcosmicToplevelManagerV1, ()> for ActivationState {
+impl Dispatch<ExtForeignToplevelListV1, ()> for ManagementState {
+    fn event(
+        state: &mut Self,
+        _list: &ExtForeignToplevelListV1,
+        event: ext_foreign_toplevel_list_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let ext_foreign_toplevel_list_v1::Event::Toplevel { toplevel } = event {
+            state.foreign.insert(toplevel.id().protocol_id(), (toplevel, WindowInfo::default()));
+        }
+    }
+
+    wayland_client::event_created_child!(ManagementState, ExtForeignToplevelListV1, [
+        ext_foreign_toplevel_list_v1::EVT_TOPLEVEL_OPCODE => (ExtForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ExtForeignToplevelHandleV1, ()> for ManagementState {
+    fn event(
+        state: &mut Self,
+        handle: &ExtForeignToplevelHandleV1,
+        event: ext_foreign_toplevel_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let handle_id = handle.id().protocol_id();
+        match event {
+            ext_foreign_toplevel_handle_v1::Event::Closed => {
+                state.foreign.remove(&handle_id);
+            }
+            event => {
+                let Some((_, window)) = state.foreign.get_mut(&handle_id) else {
+                    return;
+                };
+                match event {
+                    ext_foreign_toplevel_handle_v1::Event::Identifier { identifier } => window.identifier = identifier,
+                    ext_foreign_toplevel_handle_v1::Event::AppId { app_id } => window.app_id = app_id,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl Dispatch<ZcosmicToplevelManagerV1, ()> for ManagementState {
     fn event(
         _state: &mut Self,
         _manager: &ZcosmicToplevelManagerV1,
@@ -311,11 +558,92 @@ impl Dispatch<ZcosmicToplevelManagerV1, ()> for ActivationState {
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        // We don't need to handle capabilities - just try to activate
+        // We don't need to handle capabilities - just try the request
+    }
+}
+
+impl Dispatch<ZcosmicWorkspaceManagerV1, ()> for ManagementState {
+    fn event(
+        state: &mut Self,
+        _manager: &ZcosmicWorkspaceManagerV1,
+        event: zcosmic_workspace_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zcosmic_workspace_manager_v1::Event::WorkspaceGroup { workspace_group } = event {
+            state.workspace_groups.push(WorkspaceGroup {
+                id: workspace_group.id().protocol_id(),
+                output: None,
+                workspaces: Vec::new(),
+            });
+        }
+    }
+
+    wayland_client::event_created_child!(ManagementState, ZcosmicWorkspaceManagerV1, [
+        zcosmic_workspace_manager_v1::EVT_WORKSPACE_GROUP_OPCODE => (ZcosmicWorkspaceGroupHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZcosmicWorkspaceGroupHandleV1, ()> for ManagementState {
+    fn event(
+        state: &mut Self,
+        group: &ZcosmicWorkspaceGroupHandleV1,
+        event: zcosmic_workspace_group_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let group_id = group.id().protocol_id();
+        let Some(entry) = state.workspace_groups.iter_mut().find(|g| g.id == group_id) else {
+            return;
+        };
+
+        match event {
+            zcosmic_workspace_group_handle_v1::Event::OutputEnter { output } => {
+                entry.output = Some(output);
+            }
+            zcosmic_workspace_group_handle_v1::Event::Workspace { workspace } => {
+                entry.workspaces.push(workspace);
+            }
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(ManagementState, ZcosmicWorkspaceGroupHandleV1, [
+        zcosmic_workspace_group_handle_v1::EVT_WORKSPACE_OPCODE => (ZcosmicWorkspaceHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZcosmicWorkspaceHandleV1, ()> for ManagementState {
+    fn event(
+        state: &mut Self,
+        workspace: &ZcosmicWorkspaceHandleV1,
+        event: zcosmic_workspace_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zcosmic_workspace_handle_v1::Event::Name { name } = event {
+            state.workspace_names.insert(workspace.id().protocol_id(), name);
+        }
     }
 }
 
-impl Dispatch<WlSeat, ()> for ActivationState {
+impl Dispatch<WlOutput, ()> for ManagementState {
+    fn event(
+        _state: &mut Self,
+        _output: &WlOutput,
+        _event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // We only need the output objects themselves
+    }
+}
+
+impl Dispatch<WlSeat, ()> for ManagementState {
     fn event(
         _state: &mut Self,
         _seat: &WlSeat,
@@ -328,13 +656,11 @@ impl Dispatch<WlSeat, ()> for ActivationState {
     }
 }
 
-/// Activate a window by app_id using zcosmic_toplevel_manager_v1
-///
-/// Returns:
-/// - Ok(true) if activation was requested
-/// - Ok(false) if no matching window found
-/// - Err if protocol not supported
-pub fn activate_window_by_app_id(app_id: &str) -> Result<bool, String> {
+/// Connect and collect toplevels and workspaces, looking for a target window
+fn query_management_state(
+    app_id: &str,
+    identifier: Option<&str>,
+) -> Result<(Connection, wayland_client::EventQueue<ManagementState>, ManagementState), String> {
     let conn = Connection::connect_to_env()
         .map_err(|e| format!("Wayland connection failed: {}", e))?;
 
@@ -342,7 +668,7 @@ pub fn activate_window_by_app_id(app_id: &str) -> Result<bool, String> {
     let mut event_queue = conn.new_event_queue();
     let qh = event_queue.handle();
 
-    let mut state = ActivationState::new(app_id.to_string());
+    let mut state = ManagementState::new(app_id.to_string(), identifier.map(str::to_string));
 
     // Get the registry
     let _registry = display.get_registry(&qh, ());
@@ -351,18 +677,63 @@ pub fn activate_window_by_app_id(app_id: &str) -> Result<bool, String> {
     event_queue.roundtrip(&mut state)
         .map_err(|e| format!("Roundtrip failed: {}", e))?;
 
-    // Another roundtrip to get toplevel info
+    // Another roundtrip to get toplevels and workspace groups
     event_queue.roundtrip(&mut state)
         .map_err(|e| format!("Roundtrip failed: {}", e))?;
 
-    // One more to ensure all Done events are received
+    // Two more to ensure all Done events and workspace names are received
+    let _ = event_queue.roundtrip(&mut state);
     let _ = event_queue.roundtrip(&mut state);
 
+    // With version 2 the target is looked up in the ext foreign toplevel list,
+    // then mapped to its COSMIC handle
+    if let Some(info) = state.info.clone().filter(|info| info.version() >= 2) {
+        if let Some(foreign) = state.find_foreign() {
+            state.found_handle = Some(info.get_cosmic_toplevel(&foreign, &qh, ()));
+            // Roundtrip to receive its states
+            let _ = event_queue.roundtrip(&mut state);
+        }
+    }
+
+    Ok((conn, event_queue, state))
+}
+
+/// Activate a window by app_id using zcosmic_toplevel_manager_v1
+///
+/// Returns:
+/// - Ok(true) if activation was requested
+/// - Ok(false) if no matching window found
+/// - Err if protocol not supported
+pub fn activate_window_by_app_id(app_id: &str) -> Result<bool, String> {
+    perform(app_id, None, WindowAction::Activate)
+}
+
+/// Activate a specific window, identified by its ext foreign toplevel identifier
+///
+/// Returns the same as [`activate_window_by_app_id`].
+pub fn activate_window(window: &WindowInfo) -> Result<bool, String> {
+    perform_window_action(window, WindowAction::Activate)
+}
+
+/// Perform an action on a specific window, identified by its ext foreign
+/// toplevel identifier
+///
+/// Returns the same as [`activate_window_by_app_id`].
+pub fn perform_window_action(window: &WindowInfo, action: WindowAction) -> Result<bool, String> {
+    perform(&window.app_id, Some(&window.identifier), action)
+}
+
+fn perform(app_id: &str, identifier: Option<&str>, action: WindowAction) -> Result<bool, String> {
+    let (_conn, mut event_queue, mut state) = query_management_state(app_id, identifier)?;
+
+    let has_identifiers = state.info.as_ref().is_some_and(|info| info.version() >= 2);
+    if identifier.is_some() && !has_identifiers {
+        return Err("Acting on a single window needs zcosmic_toplevel_info_v1 version 2".to_string());
+    }
+
     // Check if we have the necessary protocol support
     let manager = state.manager.as_ref()
         .ok_or_else(|| "zcosmic_toplevel_manager_v1 not supported (COSMIC-specific feature)".to_string())?;
-    let seat = state.seat.as_ref()
-        .ok_or_else(|| "No seat available".to_string())?;
 
     // Check if we found a matching window
     let handle = match state.found_handle {
@@ -370,14 +741,96 @@ pub fn activate_window_by_app_id(app_id: &str) -> Result<bool, String> {
         None => return Ok(false),
     };
 
-    // Request activation
-    manager.activate(handle, seat);
+    match action {
+        WindowAction::Activate => {
+            let seat = state.seat.as_ref()
+                .ok_or_else(|| "No seat available".to_string())?;
+            manager.activate(handle, seat);
+        }
+        WindowAction::Close => manager.close(handle),
+        WindowAction::Minimize => manager.set_minimized(handle),
+        WindowAction::ToggleMaximize => {
+            if state.found_has_state(STATE_MAXIMIZED) {
+                manager.unset_maximized(handle);
+            } else {
+                manager.set_maximized(handle);
+            }
+        }
+        WindowAction::ToggleFullscreen => {
+            if state.found_has_state(STATE_FULLSCREEN) {
+                manager.unset_fullscreen(handle);
+            } else {
+                manager.set_fullscreen(handle, None);
+            }
+        }
+        WindowAction::MoveToWorkspace(index) => {
+            if manager.version() < 2 {
+                return Err("Moving windows between workspaces needs zcosmic_toplevel_manager_v1 version 2".to_string());
+            }
+            let workspaces = state.workspaces();
+            let (workspace, output) = workspaces
+                .get(index)
+                .ok_or_else(|| format!("No workspace {}", index + 1))?;
+            let output = output.ok_or_else(|| "Workspace has no output".to_string())?;
+            manager.move_to_workspace(handle, workspace, output);
+        }
+    }
 
-    // Roundtrip to process the activation
+    // Roundtrip to process the request
     let _ = event_queue.roundtrip(&mut state);
 
-    // Small delay to ensure activation completes
+    // Small delay to ensure the request completes
     std::thread::sleep(std::time::Duration::from_millis(50));
 
     Ok(true)
 }
+
+/// Get the names of all workspaces, in the order used by
+/// [`WindowAction::MoveToWorkspace`]
+pub fn get_workspaces() -> Vec<String> {
+    match query_management_state("", None) {
+        Ok((_conn, _queue, state)) => state.workspace_labels(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Query workspace names via subprocess to avoid Wayland connection conflicts
+pub fn query_workspaces_via_subprocess() -> Vec<String> {
+    let exe = std::env::current_exe().unwrap_or_else(|_| "cosmic-pie-menu".into());
+    match std::process::Command::new(&exe).arg("--query-workspaces").output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to query workspaces: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_line_roundtrip() {
+        let window = WindowInfo {
+            identifier: "a1b2c3".to_string(),
+            app_id: "firefox".to_string(),
+            title: "Tabs\tand: colons".to_string(),
+        };
+        let line = format_window_line(&window);
+        assert_eq!(line, "a1b2c3\tfirefox\tTabs and: colons");
+        assert_eq!(
+            parse_window_line(&line),
+            Some(WindowInfo {
+                identifier: "a1b2c3".to_string(),
+                app_id: "firefox".to_string(),
+                title: "Tabs and: colons".to_string(),
+            })
+        );
+        assert_eq!(parse_window_line("a1b2c3\t\tuntitled"), None);
+        assert_eq!(parse_window_line("firefox\tno identifier"), None);
+    }
+}